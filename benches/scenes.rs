@@ -0,0 +1,24 @@
+//! Times a small render of every scene preset. Run with `cargo bench`.
+
+use std::time::Instant;
+
+use rust_tracer::{scenes, Integrator};
+
+fn main() {
+    for (name, build_scene) in scenes::PRESETS {
+        let camera = build_scene().camera;
+
+        let now = Instant::now();
+        rust_tracer::render_image(
+            64,
+            8,
+            8,
+            move || build_scene().world,
+            camera,
+            4,
+            Integrator::Rgb,
+        );
+
+        eprintln!("\n{name}: {:.3} seconds", now.elapsed().as_secs_f64());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::math::{Point3, Ray, Vector3};
+
+/// An axis-aligned bounding box, used to skip objects a ray can't possibly hit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub minimum: Point3,
+    pub maximum: Point3,
+}
+
+impl Aabb {
+    /// Creates the box spanned by two opposite corners, given in any order.
+    pub fn new(a: Point3, b: Point3) -> Self {
+        Self {
+            minimum: a.min(&b),
+            maximum: a.max(&b),
+        }
+    }
+
+    pub fn surrounding(a: Aabb, b: Aabb) -> Self {
+        Self {
+            minimum: a.minimum.min(&b.minimum),
+            maximum: a.maximum.max(&b.maximum),
+        }
+    }
+
+    /// Grows any degenerate (zero-thickness) side so that flat objects like quads still get hit.
+    pub fn padded(&self) -> Self {
+        let delta = 0.0001;
+        let mut minimum = self.minimum;
+        let mut maximum = self.maximum;
+
+        for (min, max) in [
+            (&mut minimum.0, &mut maximum.0),
+            (&mut minimum.1, &mut maximum.1),
+            (&mut minimum.2, &mut maximum.2),
+        ] {
+            if *max - *min < delta {
+                *min -= delta / 2.0;
+                *max += delta / 2.0;
+            }
+        }
+
+        Self { minimum, maximum }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.minimum + self.maximum) / 2.0
+    }
+
+    pub fn size(&self) -> Vector3 {
+        self.maximum - self.minimum
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let size = self.size();
+
+        if size.x() > size.y() && size.x() > size.z() {
+            0
+        } else if size.y() > size.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        let (a, b) = (self.minimum, self.maximum);
+
+        [
+            Point3::new(a.x(), a.y(), a.z()),
+            Point3::new(b.x(), a.y(), a.z()),
+            Point3::new(a.x(), b.y(), a.z()),
+            Point3::new(b.x(), b.y(), a.z()),
+            Point3::new(a.x(), a.y(), b.z()),
+            Point3::new(b.x(), a.y(), b.z()),
+            Point3::new(a.x(), b.y(), b.z()),
+            Point3::new(b.x(), b.y(), b.z()),
+        ]
+    }
+
+    /// Returns the parametric interval of the ray that lies inside the box, if any.
+    pub fn clip(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction[axis];
+            let mut t0 = (self.minimum[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.maximum[axis] - ray.origin[axis]) * inv_d;
+
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+
+            if t_max <= t_min {
+                return None;
+            }
+        }
+
+        Some((t_min, t_max))
+    }
+
+    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+}
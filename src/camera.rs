@@ -1,12 +1,159 @@
-use rand::rngs::ThreadRng;
+use std::{f64::consts::PI, sync::Arc};
+
+use rand::{rngs::ThreadRng, Rng};
 
 use crate::{
+    hittables::Hittable,
+    image::Image,
+    lens::LensSystem,
     math::{Point3, Ray, Vector3},
-    utils::random_in_unit_disk,
+    spectrum::DEFAULT_WAVELENGTH,
+    utils::{luminance, random_in_unit_disk},
 };
 
-#[derive(Clone, Copy)]
-pub struct Camera {
+/// Turns points on the image into the rays that light arriving there travelled along.
+pub trait Camera: Send + Sync {
+    /// The ray through the point `(s, t)` of the image, where both run from 0 to 1 and `t` goes up from the bottom.
+    ///
+    /// Returns `None` for points the camera doesn't see anything at, which stay black, such as the corners around
+    /// a fisheye's circular image.
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray>;
+
+    /// The width of the image over its height.
+    fn aspect_ratio(&self) -> f64;
+
+    /// Like `ray`, for light of the given `wavelength`. Cameras whose lenses bend light differently depending on its
+    /// wavelength return a `dispersed` ray, which only carries light at that wavelength.
+    fn ray_at_wavelength(
+        &self,
+        s: f64,
+        t: f64,
+        wavelength: f64,
+        rng: &mut ThreadRng,
+    ) -> Option<Ray> {
+        self.ray(s, t, rng).map(|ray| Ray { wavelength, ..ray })
+    }
+
+    /// How much the light reaching the camera is scaled by on its way into the image.
+    fn exposure(&self) -> f64 {
+        1.0
+    }
+}
+
+/// The settings that decide how bright a real camera's photos come out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Exposure {
+    /// The sensor's sensitivity to light (ISO speed).
+    pub iso: f64,
+    /// How long the shutter stays open, in seconds.
+    pub shutter_speed: f64,
+    /// The lens's focal length over the diameter of its aperture. Each time it doubles, a quarter as much light gets
+    /// through.
+    pub f_number: f64,
+}
+
+impl Exposure {
+    pub fn new(iso: f64, shutter_speed: f64, f_number: f64) -> Self {
+        Exposure {
+            iso,
+            shutter_speed,
+            f_number,
+        }
+    }
+
+    /// The exposure value at ISO 100, which photographers use to compare settings; one more lets in half the light.
+    pub fn ev100(&self) -> f64 {
+        (self.f_number * self.f_number / self.shutter_speed * 100.0 / self.iso).log2()
+    }
+
+    /// The factor that turns luminance in the scene, in candelas per square metre, into image values, where 1 is the
+    /// brightest the sensor records.
+    ///
+    /// This is the saturation-based sensitivity of ISO 12232, for a lens that lets 65% of the light through.
+    pub fn scale(&self) -> f64 {
+        self.iso * self.shutter_speed / (78.0 / 0.65 * self.f_number * self.f_number)
+    }
+}
+
+/// A random time between `open` and `close`, for sending out a ray while the shutter is open.
+fn shutter_time(open: f64, close: f64, rng: &mut ThreadRng) -> f64 {
+    if close > open {
+        rng.gen_range(open..close)
+    } else {
+        open
+    }
+}
+
+/// The shape of the opening light passes through on its way into a lens, which out-of-focus highlights (bokeh) take
+/// on.
+#[derive(Clone, Default)]
+pub enum Aperture {
+    /// A perfectly round opening.
+    #[default]
+    Circle,
+    /// The regular polygon left open by `blades` straight diaphragm blades, turned `rotation` degrees.
+    Polygon { blades: u32, rotation: f64 },
+    /// An image stretched over the lens, letting light through in proportion to its brightness, like a cardboard
+    /// cut-out held in front of it.
+    Mask(Arc<Image>),
+}
+
+impl Aperture {
+    /// A random point on the opening, within the unit disk.
+    fn sample(&self, rng: &mut ThreadRng) -> (f64, f64) {
+        match self {
+            Aperture::Circle => {
+                let p = random_in_unit_disk(rng);
+
+                (p.x(), p.y())
+            }
+            Aperture::Polygon { blades, rotation } => {
+                // Pick one of the triangles fanning out from the centre, then a point on it
+                let blades = (*blades).max(3);
+                let step = 2.0 * PI / blades as f64;
+                let start = rotation.to_radians() + step * rng.gen_range(0..blades) as f64;
+
+                let (mut a, mut b) = (rng.gen::<f64>(), rng.gen::<f64>());
+
+                if a + b > 1.0 {
+                    (a, b) = (1.0 - a, 1.0 - b);
+                }
+
+                (
+                    a * start.cos() + b * (start + step).cos(),
+                    a * start.sin() + b * (start + step).sin(),
+                )
+            }
+            Aperture::Mask(image) => {
+                // Give up on masks that are almost entirely dark and fall back to a pinhole
+                for _ in 0..64 {
+                    let (x, y) = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+
+                    if x * x + y * y >= 1.0 {
+                        continue;
+                    }
+
+                    // Images are stored from the top row down
+                    let column =
+                        (((x + 1.0) / 2.0 * image.width as f64) as u32).min(image.width - 1);
+                    let row =
+                        (((1.0 - y) / 2.0 * image.height as f64) as u32).min(image.height - 1);
+                    let opacity = luminance(image.pixels[(row * image.width + column) as usize]);
+
+                    if rng.gen::<f64>() < opacity {
+                        return (x, y);
+                    }
+                }
+
+                (0.0, 0.0)
+            }
+        }
+    }
+}
+
+/// A pinhole or thin-lens camera, where things further away look smaller.
+#[derive(Clone)]
+pub struct PerspectiveCamera {
     origin: Point3,
     lower_left_corner: Point3,
     horizontal: Vector3,
@@ -14,17 +161,31 @@ pub struct Camera {
     u: Vector3,
     v: Vector3,
     lens_radius: f64,
+    aperture: Aperture,
+    cat_eye: f64,
+    anamorphic_squeeze: f64,
+    /// The normal of the plane in focus, when the lens is tilted so that it doesn't face the camera.
+    focal_plane: Option<Vector3>,
+    exposure: f64,
     pub aspect_ratio: f64,
+    /// When the shutter opens; rays are sent out at random times between this and `shutter_close`.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
 }
 
-impl Camera {
+impl PerspectiveCamera {
+    /// Starts building a camera with named settings, instead of passing them all to `new`.
+    pub fn builder() -> PerspectiveCameraBuilder {
+        PerspectiveCameraBuilder::default()
+    }
+
     pub fn new(
         lookfrom: Point3,
         lookat: Point3,
         vup: Vector3,
         vfov: f64,
         aspect_ratio: f64,
-        apeture: f64,
+        aperture: f64,
         focus_dist: f64,
     ) -> Self {
         let viewport_height = 2.0 * (vfov.to_radians() / 2.0).tan();
@@ -38,9 +199,9 @@ impl Camera {
         let horizontal = focus_dist * viewport_width * u;
         let vertical = focus_dist * viewport_height * v;
 
-        let lens_radius = apeture / 2.0;
+        let lens_radius = aperture / 2.0;
 
-        Camera {
+        PerspectiveCamera {
             origin,
             horizontal,
             vertical,
@@ -49,16 +210,1274 @@ impl Camera {
             u,
             v,
             lens_radius,
+            aperture: Aperture::Circle,
+            cat_eye: 0.0,
+            anamorphic_squeeze: 1.0,
+            focal_plane: None,
+            exposure: 1.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+
+    /// Gives the lens an opening of the given shape, still as wide as the aperture passed to `new`.
+    pub fn with_aperture(self, aperture: Aperture) -> Self {
+        PerspectiveCamera { aperture, ..self }
+    }
+
+    /// Clips the aperture with the lens barrel towards the edges of the image, so highlights there are cut into
+    /// cat's-eye shapes and the corners darken. At a `strength` of 1 the barrel's edge runs through the middle of the
+    /// aperture in the corners.
+    pub fn with_cat_eye(self, strength: f64) -> Self {
+        PerspectiveCamera {
+            cat_eye: strength,
+            ..self
+        }
+    }
+
+    /// Squeezes the aperture horizontally by `ratio`, as an anamorphic lens does, so highlights become tall ovals.
+    pub fn with_anamorphic_squeeze(self, ratio: f64) -> Self {
+        PerspectiveCamera {
+            anamorphic_squeeze: ratio,
+            ..self
+        }
+    }
+
+    /// Shifts the lens `x` image widths to the right and `y` image heights up, which moves the picture without
+    /// turning the camera. Lines parallel to the film stay parallel, so a level camera shifted up takes in a tall
+    /// building without its walls leaning in.
+    pub fn with_shift(self, x: f64, y: f64) -> Self {
+        PerspectiveCamera {
+            lower_left_corner: self.lower_left_corner + x * self.horizontal + y * self.vertical,
+            ..self
+        }
+    }
+
+    /// Tilts the lens, which turns the plane in focus away from facing the camera (the Scheimpflug principle). Its
+    /// top leans `tilt` degrees away from the camera, and its right side `swing` degrees, turning about the point in
+    /// focus straight ahead. Tilting it to lie along the ground brings all of the ground into focus at once.
+    pub fn with_tilt(self, tilt: f64, swing: f64) -> Self {
+        let forward = Vector3::cross(&self.v, &self.u);
+        let normal = forward - tilt.to_radians().tan() * self.v - swing.to_radians().tan() * self.u;
+
+        PerspectiveCamera {
+            focal_plane: Some(normal.unit_vector()),
+            ..self
+        }
+    }
+
+    /// Keeps the shutter open from `open` to `close`, so anything moving in that time is motion blurred.
+    pub fn with_shutter(self, open: f64, close: f64) -> Self {
+        PerspectiveCamera {
+            shutter_open: open,
+            shutter_close: close,
+            ..self
+        }
+    }
+
+    /// Exposes the image with the given settings, taking light in the scene to be in candelas per square metre.
+    ///
+    /// The f-number also sets the size of the aperture, for a full-frame camera (with a sensor 24mm tall) with the
+    /// same field of view, where `millimetre` is the size of a millimetre in scene units.
+    pub fn with_exposure(self, exposure: Exposure, millimetre: f64) -> Self {
+        let focal_length = 24.0 * self.focus_dist() / self.vertical.length();
+
+        PerspectiveCamera {
+            lens_radius: focal_length / exposure.f_number / 2.0 * millimetre,
+            exposure: exposure.scale(),
+            ..self
+        }
+    }
+
+    /// The same camera moved `offset` to the right, with its view shifted (not turned) so that the centres of both
+    /// images line up at `convergence` distance away.
+    fn eye(&self, offset: f64, convergence: f64) -> Self {
+        PerspectiveCamera {
+            origin: self.origin + offset * self.u,
+            lower_left_corner: self.lower_left_corner
+                + offset * (1.0 - self.focus_dist() / convergence) * self.u,
+            ..self.clone()
+        }
+    }
+
+    /// Focuses on whatever is seen at the point `(s, t)` of the image, by casting a ray through it into `world`.
+    /// Keeps the current focus if the ray hits nothing.
+    pub fn autofocus(self, world: &dyn Hittable, s: f64, t: f64) -> Self {
+        let through = self.lower_left_corner + s * self.horizontal + t * self.vertical;
+        let ray = Ray::new(self.origin, through - self.origin);
+
+        let Some(rec) = world.hit(ray, 0.001, f64::INFINITY) else {
+            return self;
+        };
+
+        // Things are in focus on a plane facing the camera, rather than at a fixed distance in every direction
+        let focus_dist = (rec.point - self.origin).dot(&Vector3::cross(&self.v, &self.u));
+
+        self.with_focus_dist(focus_dist)
+    }
+
+    /// The same camera focused `focus_dist` away, with the window rays are aimed through moved to that distance.
+    fn with_focus_dist(self, focus_dist: f64) -> Self {
+        let scale = focus_dist / self.focus_dist();
+
+        PerspectiveCamera {
+            lower_left_corner: self.origin + scale * (self.lower_left_corner - self.origin),
+            horizontal: scale * self.horizontal,
+            vertical: scale * self.vertical,
+            ..self
         }
     }
 
-    pub fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Ray {
-        let focus_disk = self.lens_radius * random_in_unit_disk(rng);
-        let offset = self.u * focus_disk.x() + self.v * focus_disk.y();
+    /// How far in front of the camera things are in focus.
+    fn focus_dist(&self) -> f64 {
+        let window_center = self.lower_left_corner + self.horizontal / 2.0 + self.vertical / 2.0;
 
-        Ray::new(
+        (window_center - self.origin).dot(&Vector3::cross(&self.v, &self.u))
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray> {
+        let (x, y) = self.aperture.sample(rng);
+
+        if self.cat_eye > 0.0 {
+            // The barrel is a disk as wide as the aperture, shifted towards the point on the image, scaled so the
+            // corners are 1 away from the centre; light outside it is blocked
+            let (image_x, image_y) = ((2.0 * s - 1.0) * self.aspect_ratio, 2.0 * t - 1.0);
+            let scale = self.cat_eye / (self.aspect_ratio * self.aspect_ratio + 1.0).sqrt();
+            let (dx, dy) = (x - scale * image_x, y - scale * image_y);
+
+            if dx * dx + dy * dy > 1.0 {
+                return None;
+            }
+        }
+
+        let offset = self.lens_radius * (self.u * (x / self.anamorphic_squeeze) + self.v * y);
+        let target = self.lower_left_corner + s * self.horizontal + t * self.vertical;
+        let mut direction = target - self.origin - offset;
+
+        // Aim at where the ray through the centre of the lens meets the tilted plane in focus, instead of the window.
+        // Rays that never reach the plane are focused at infinity.
+        if let Some(normal) = self.focal_plane {
+            let through = target - self.origin;
+            let forward = Vector3::cross(&self.v, &self.u);
+            let reach = self.focus_dist() * forward.dot(&normal) / through.dot(&normal);
+
+            direction = if reach > 0.0 {
+                reach * through - offset
+            } else {
+                through
+            };
+        }
+
+        Some(Ray::with_time(
             self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            direction,
+            shutter_time(self.shutter_open, self.shutter_close, rng),
+        ))
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+
+    fn exposure(&self) -> f64 {
+        self.exposure
+    }
+}
+
+/// How much of the scene a [`PerspectiveCamera`] takes in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldOfView {
+    /// The angle in degrees between the left and right edges of the image.
+    Horizontal(f64),
+    /// The angle in degrees between the top and bottom edges of the image.
+    Vertical(f64),
+    /// The angle in degrees between opposite corners of the image.
+    Diagonal(f64),
+    /// The view of a lens with the given focal length on a sensor `sensor_height` tall, both in millimetres, such as
+    /// a 50mm lens on a full-frame sensor 24mm tall.
+    FocalLength {
+        focal_length: f64,
+        sensor_height: f64,
+    },
+}
+
+impl FieldOfView {
+    /// The vertical field of view in degrees, for an image `aspect_ratio` times as wide as it is tall.
+    pub fn vertical(&self, aspect_ratio: f64) -> f64 {
+        // Half the height of the image, one unit in front of the camera
+        let half_height = match *self {
+            FieldOfView::Horizontal(angle) => (angle.to_radians() / 2.0).tan() / aspect_ratio,
+            FieldOfView::Vertical(angle) => (angle.to_radians() / 2.0).tan(),
+            FieldOfView::Diagonal(angle) => {
+                (angle.to_radians() / 2.0).tan() / aspect_ratio.hypot(1.0)
+            }
+            FieldOfView::FocalLength {
+                focal_length,
+                sensor_height,
+            } => sensor_height / 2.0 / focal_length,
+        };
+
+        2.0 * half_height.atan().to_degrees()
+    }
+}
+
+/// Builds a [`PerspectiveCamera`] from named settings. Anything not set is left as in Ray Tracing in One Weekend:
+/// looking from the origin down `-z` with a 90 degree vertical field of view, a 16:9 image and no depth of field.
+#[derive(Clone, Debug)]
+pub struct PerspectiveCameraBuilder {
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vector3,
+    roll: f64,
+    field_of_view: FieldOfView,
+    aspect_ratio: f64,
+    aperture: f64,
+    focus_dist: Option<f64>,
+    shift: (f64, f64),
+    tilt: (f64, f64),
+}
+
+impl Default for PerspectiveCameraBuilder {
+    fn default() -> Self {
+        PerspectiveCameraBuilder {
+            lookfrom: Point3::new(0.0, 0.0, 0.0),
+            lookat: Point3::new(0.0, 0.0, -1.0),
+            vup: Vector3::new(0.0, 1.0, 0.0),
+            roll: 0.0,
+            field_of_view: FieldOfView::Vertical(90.0),
+            aspect_ratio: 16.0 / 9.0,
+            aperture: 0.0,
+            focus_dist: None,
+            shift: (0.0, 0.0),
+            tilt: (0.0, 0.0),
+        }
+    }
+}
+
+impl PerspectiveCameraBuilder {
+    pub fn look_from(self, lookfrom: Point3) -> Self {
+        PerspectiveCameraBuilder { lookfrom, ..self }
+    }
+
+    pub fn look_at(self, lookat: Point3) -> Self {
+        PerspectiveCameraBuilder { lookat, ..self }
+    }
+
+    /// The direction that appears straight up in the image, before it's rolled.
+    pub fn up(self, vup: Vector3) -> Self {
+        PerspectiveCameraBuilder { vup, ..self }
+    }
+
+    /// Turns the camera `degrees` clockwise around the direction it looks in, tilting the horizon.
+    pub fn roll(self, degrees: f64) -> Self {
+        PerspectiveCameraBuilder {
+            roll: degrees,
+            ..self
+        }
+    }
+
+    pub fn field_of_view(self, field_of_view: FieldOfView) -> Self {
+        PerspectiveCameraBuilder {
+            field_of_view,
+            ..self
+        }
+    }
+
+    pub fn aspect_ratio(self, aspect_ratio: f64) -> Self {
+        PerspectiveCameraBuilder {
+            aspect_ratio,
+            ..self
+        }
+    }
+
+    /// The diameter of the lens, in scene units; the wider it is, the blurrier things out of focus are.
+    pub fn aperture(self, aperture: f64) -> Self {
+        PerspectiveCameraBuilder { aperture, ..self }
+    }
+
+    /// How far in front of the camera things are in focus. Defaults to the distance to the point looked at; see
+    /// [`PerspectiveCamera::autofocus`] to focus on whatever is at some point in the image.
+    pub fn focus_distance(self, focus_dist: f64) -> Self {
+        PerspectiveCameraBuilder {
+            focus_dist: Some(focus_dist),
+            ..self
+        }
+    }
+
+    /// See [`PerspectiveCamera::with_shift`].
+    pub fn shift(self, x: f64, y: f64) -> Self {
+        PerspectiveCameraBuilder {
+            shift: (x, y),
+            ..self
+        }
+    }
+
+    /// See [`PerspectiveCamera::with_tilt`].
+    pub fn tilt(self, tilt: f64, swing: f64) -> Self {
+        PerspectiveCameraBuilder {
+            tilt: (tilt, swing),
+            ..self
+        }
+    }
+
+    pub fn build(self) -> PerspectiveCamera {
+        let w = (self.lookfrom - self.lookat).unit_vector();
+        let u = Vector3::cross(&self.vup, &w).unit_vector();
+        let v = Vector3::cross(&w, &u);
+        let roll = self.roll.to_radians();
+
+        let camera = PerspectiveCamera::new(
+            self.lookfrom,
+            self.lookat,
+            roll.cos() * v + roll.sin() * u,
+            self.field_of_view.vertical(self.aspect_ratio),
+            self.aspect_ratio,
+            self.aperture,
+            self.focus_dist
+                .unwrap_or_else(|| (self.lookat - self.lookfrom).length()),
         )
+        .with_shift(self.shift.0, self.shift.1);
+
+        if self.tilt == (0.0, 0.0) {
+            camera
+        } else {
+            camera.with_tilt(self.tilt.0, self.tilt.1)
+        }
+    }
+}
+
+/// A camera without perspective, where all rays run parallel and things keep their size however far away they are,
+/// as in technical drawings and isometric views.
+#[derive(Clone, Copy)]
+pub struct OrthographicCamera {
+    lower_left_corner: Point3,
+    horizontal: Vector3,
+    vertical: Vector3,
+    direction: Vector3,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl OrthographicCamera {
+    /// A camera looking from `lookfrom` towards `lookat` that sees a `view_width` by `view_height` rectangle of the
+    /// scene, centred on the line between them.
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vector3,
+        view_width: f64,
+        view_height: f64,
+    ) -> Self {
+        let w = (lookfrom - lookat).unit_vector();
+        let u = Vector3::cross(&vup, &w).unit_vector();
+        let v = Vector3::cross(&w, &u);
+
+        let horizontal = view_width * u;
+        let vertical = view_height * v;
+
+        OrthographicCamera {
+            lower_left_corner: lookfrom - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            direction: -w,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+
+    /// Keeps the shutter open from `open` to `close`, so anything moving in that time is motion blurred.
+    pub fn with_shutter(self, open: f64, close: f64) -> Self {
+        OrthographicCamera {
+            shutter_open: open,
+            shutter_close: close,
+            ..self
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray> {
+        Some(Ray::with_time(
+            self.lower_left_corner + s * self.horizontal + t * self.vertical,
+            self.direction,
+            shutter_time(self.shutter_open, self.shutter_close, rng),
+        ))
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.horizontal.length() / self.vertical.length()
+    }
+}
+
+/// How a [`PanoramicCamera`] lays out the directions around it on the image. Angles are in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Longitude across the image and latitude up it. Covering 360 by 180 degrees gives the usual layout for
+    /// environment maps, which [`Background::Environment`](crate::hittables::Background::Environment) reads.
+    Equirectangular {
+        horizontal_fov: f64,
+        vertical_fov: f64,
+    },
+    /// Longitude across the image like an equirectangular projection, but perspective up it, so vertical lines stay
+    /// straight.
+    Cylindrical {
+        horizontal_fov: f64,
+        vertical_fov: f64,
+    },
+    /// A circular image where the distance from the centre is proportional to the angle from the view direction.
+    EquidistantFisheye { fov: f64 },
+    /// A circular image where equal areas cover equal solid angles, like most real fisheye lenses.
+    EquisolidFisheye { fov: f64 },
+}
+
+impl Projection {
+    /// The full sphere of directions, as an environment map.
+    pub fn full_sphere() -> Self {
+        Projection::Equirectangular {
+            horizontal_fov: 360.0,
+            vertical_fov: 180.0,
+        }
+    }
+
+    /// The direction seen at `(s, t)`, in a frame where the camera looks down `-z` with `y` up.
+    fn direction(&self, s: f64, t: f64) -> Option<Vector3> {
+        // Longitude to the right of the view direction
+        let around = |angle: f64| {
+            let phi = (s - 0.5) * angle.to_radians();
+
+            (phi.sin(), -phi.cos())
+        };
+
+        match *self {
+            Projection::Equirectangular {
+                horizontal_fov,
+                vertical_fov,
+            } => {
+                let (x, z) = around(horizontal_fov);
+                let theta = (t - 0.5) * vertical_fov.to_radians();
+
+                Some(Vector3::new(theta.cos() * x, theta.sin(), theta.cos() * z))
+            }
+            Projection::Cylindrical {
+                horizontal_fov,
+                vertical_fov,
+            } => {
+                let (x, z) = around(horizontal_fov);
+                let height = (vertical_fov.to_radians() / 2.0).tan();
+
+                Some(Vector3::new(x, (2.0 * t - 1.0) * height, z))
+            }
+            Projection::EquidistantFisheye { fov } | Projection::EquisolidFisheye { fov } => {
+                let (x, y) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+                let radius = (x * x + y * y).sqrt();
+
+                if radius > 1.0 {
+                    return None;
+                }
+
+                let half_fov = fov.to_radians() / 2.0;
+                let theta = match self {
+                    Projection::EquidistantFisheye { .. } => radius * half_fov,
+                    _ => 2.0 * (radius * (half_fov / 2.0).sin()).asin(),
+                };
+                let (x, y) = if radius > 0.0 {
+                    (x / radius, y / radius)
+                } else {
+                    (0.0, 0.0)
+                };
+
+                Some(Vector3::new(theta.sin() * x, theta.sin() * y, -theta.cos()))
+            }
+        }
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        match *self {
+            Projection::Equirectangular {
+                horizontal_fov,
+                vertical_fov,
+            } => horizontal_fov / vertical_fov,
+            Projection::Cylindrical {
+                horizontal_fov,
+                vertical_fov,
+            } => horizontal_fov.to_radians() / (2.0 * (vertical_fov.to_radians() / 2.0).tan()),
+            Projection::EquidistantFisheye { .. } | Projection::EquisolidFisheye { .. } => 1.0,
+        }
+    }
+}
+
+/// Where a direction lands on a full-sphere equirectangular image looking down `-z`, as `(s, t)` coordinates
+/// running from 0 to 1 with `t` going up.
+pub fn equirectangular_coordinates(direction: Vector3) -> (f64, f64) {
+    let direction = direction.unit_vector();
+    let phi = direction.x().atan2(-direction.z());
+    let theta = direction.y().clamp(-1.0, 1.0).asin();
+
+    (phi / (2.0 * PI) + 0.5, theta / PI + 0.5)
+}
+
+/// A camera that sees more than a perspective camera can, up to everything around it, such as for rendering
+/// environment maps and VR previews.
+#[derive(Clone, Copy)]
+pub struct PanoramicCamera {
+    origin: Point3,
+    u: Vector3,
+    v: Vector3,
+    w: Vector3,
+    pub projection: Projection,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    /// For omni-directional stereo, how far to the right of `origin` each ray leaves from, on a circle around it.
+    eye_offset: f64,
+    /// How far away rays from the eyes of an omni-directional stereo rig cross.
+    convergence: f64,
+}
+
+impl PanoramicCamera {
+    /// A camera at `lookfrom` with `lookat` in the centre of the image.
+    pub fn new(lookfrom: Point3, lookat: Point3, vup: Vector3, projection: Projection) -> Self {
+        let w = (lookfrom - lookat).unit_vector();
+        let u = Vector3::cross(&vup, &w).unit_vector();
+        let v = Vector3::cross(&w, &u);
+
+        PanoramicCamera {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            projection,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            eye_offset: 0.0,
+            convergence: f64::INFINITY,
+        }
+    }
+
+    /// Keeps the shutter open from `open` to `close`, so anything moving in that time is motion blurred.
+    pub fn with_shutter(self, open: f64, close: f64) -> Self {
+        PanoramicCamera {
+            shutter_open: open,
+            shutter_close: close,
+            ..self
+        }
+    }
+
+    /// One eye of an omni-directional stereo rig: whichever way a ray looks, it leaves from `offset` to the right
+    /// of the centre, as if the viewer had turned their head to face that way.
+    fn eye(self, offset: f64, convergence: f64) -> Self {
+        PanoramicCamera {
+            eye_offset: offset,
+            convergence,
+            ..self
+        }
+    }
+}
+
+impl Camera for PanoramicCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray> {
+        let local = self.projection.direction(s, t)?;
+        let mut direction = local.x() * self.u + local.y() * self.v + local.z() * self.w;
+        let mut origin = self.origin;
+
+        // Straight up and down there's no way to face, so the eyes meet
+        let horizontal = (local.x() * local.x() + local.z() * local.z()).sqrt();
+        if self.eye_offset != 0.0 && horizontal > 0.0 {
+            let right = (-local.z() * self.u + local.x() * self.w) / horizontal;
+            let offset = self.eye_offset * horizontal * right;
+
+            origin += offset;
+            if self.convergence.is_finite() {
+                direction = self.convergence * direction.unit_vector() - offset;
+            }
+        }
+
+        Some(Ray::with_time(
+            origin,
+            direction,
+            shutter_time(self.shutter_open, self.shutter_close, rng),
+        ))
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.projection.aspect_ratio()
+    }
+}
+
+/// How many rings around the centre of the film [`RealisticCamera`] finds the exit pupil for.
+const PUPIL_RINGS: usize = 32;
+
+/// A camera that traces rays through every element of a real lens, so its images have the distortion, vignetting
+/// and chromatic aberration of that lens.
+#[derive(Clone)]
+pub struct RealisticCamera {
+    origin: Point3,
+    u: Vector3,
+    v: Vector3,
+    w: Vector3,
+    lens: LensSystem,
+    film_width: f64,
+    film_height: f64,
+    millimetre: f64,
+    /// The bounds of the points on the rear element that light reaches the film through (the exit pupil), for
+    /// points on the film along `+x` in each ring, or `None` where the lens blocks all light.
+    exit_pupil: Vec<Option<[f64; 4]>>,
+    pupil_area: f64,
+    exposure: f64,
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+impl RealisticCamera {
+    /// A camera with its film centred on `lookfrom` and its `lens` pointing and focused at `lookat`. The film is
+    /// `film_diagonal` millimetres across (43.3 for 35mm film), and `millimetre` is the size of a millimetre in scene
+    /// units.
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vector3,
+        mut lens: LensSystem,
+        film_diagonal: f64,
+        aspect_ratio: f64,
+        millimetre: f64,
+    ) -> Self {
+        let w = (lookfrom - lookat).unit_vector();
+        let u = Vector3::cross(&vup, &w).unit_vector();
+        let v = Vector3::cross(&w, &u);
+
+        lens.focus((lookat - lookfrom).length() / millimetre);
+
+        let film_height = film_diagonal / (aspect_ratio * aspect_ratio + 1.0).sqrt();
+        let (exit_pupil, pupil_area) = Self::exit_pupil(&lens, film_diagonal / 2.0);
+
+        RealisticCamera {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            lens,
+            film_width: aspect_ratio * film_height,
+            film_height,
+            millimetre,
+            exit_pupil,
+            pupil_area,
+            exposure: 1.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+
+    /// Keeps the shutter open from `open` to `close`, so anything moving in that time is motion blurred.
+    pub fn with_shutter(self, open: f64, close: f64) -> Self {
+        RealisticCamera {
+            shutter_open: open,
+            shutter_close: close,
+            ..self
+        }
+    }
+
+    /// Exposes the image with the given settings, taking light in the scene to be in candelas per square metre. The
+    /// lens's aperture stop is resized to match the f-number.
+    pub fn with_exposure(mut self, exposure: Exposure) -> Self {
+        self.lens.set_f_number(exposure.f_number);
+
+        let film_radius = self.film_width.hypot(self.film_height) / 2.0;
+        let (exit_pupil, pupil_area) = Self::exit_pupil(&self.lens, film_radius);
+
+        RealisticCamera {
+            exit_pupil,
+            pupil_area,
+            exposure: exposure.scale(),
+            ..self
+        }
+    }
+
+    /// Finds the exit pupil of each ring by tracing a grid of rays from across the ring through the rear element,
+    /// so that rays can be aimed where they'll make it through the lens. Also returns the area of the pupil at the
+    /// centre, which the others are compared to.
+    fn exit_pupil(lens: &LensSystem, film_radius: f64) -> (Vec<Option<[f64; 4]>>, f64) {
+        let grid = 48;
+        let rear = lens.rear_element();
+        let extent = 1.5 * rear.aperture_radius;
+        let cell = 2.0 * extent / grid as f64;
+
+        let exit_pupil: Vec<_> = (0..PUPIL_RINGS)
+            .map(|ring| {
+                let mut bounds: Option<[f64; 4]> = None;
+
+                for step in 0..3 {
+                    let film = Point3::new(
+                        film_radius * (ring as f64 + step as f64 / 2.0) / PUPIL_RINGS as f64,
+                        0.0,
+                        0.0,
+                    );
+
+                    for i in 0..grid {
+                        for j in 0..grid {
+                            let x = -extent + (i as f64 + 0.5) * cell;
+                            let y = -extent + (j as f64 + 0.5) * cell;
+                            let ray = Ray::new(film, Point3::new(x, y, rear.thickness) - film);
+
+                            if lens.trace(ray, true).is_none() {
+                                continue;
+                            }
+
+                            bounds = Some(match bounds {
+                                None => [x, x, y, y],
+                                Some([x0, x1, y0, y1]) => {
+                                    [x0.min(x), x1.max(x), y0.min(y), y1.max(y)]
+                                }
+                            });
+                        }
+                    }
+                }
+
+                // Pad by a grid cell so that the edges of the pupil aren't cut off
+                bounds.map(|[x0, x1, y0, y1]| [x0 - cell, x1 + cell, y0 - cell, y1 + cell])
+            })
+            .collect();
+        let pupil_area = exit_pupil[0].map_or(0.0, |[x0, x1, y0, y1]| (x1 - x0) * (y1 - y0));
+
+        (exit_pupil, pupil_area)
+    }
+}
+
+impl Camera for RealisticCamera {
+    /// Traces the ray without chromatic aberration, at a single wavelength.
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray> {
+        self.ray_at_wavelength(s, t, DEFAULT_WAVELENGTH, rng)
+            .map(|ray| Ray {
+                dispersed: false,
+                ..ray
+            })
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.film_width / self.film_height
+    }
+
+    fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    fn ray_at_wavelength(
+        &self,
+        s: f64,
+        t: f64,
+        wavelength: f64,
+        rng: &mut ThreadRng,
+    ) -> Option<Ray> {
+        // The lens turns the image upside down, so the top right of the image is at the bottom left of the film
+        let film = Point3::new(
+            (0.5 - s) * self.film_width,
+            (0.5 - t) * self.film_height,
+            0.0,
+        );
+        let radius = film.length();
+        let film_radius = self.film_width.hypot(self.film_height) / 2.0;
+        let ring = ((radius / film_radius * PUPIL_RINGS as f64) as usize).min(PUPIL_RINGS - 1);
+        let [x0, x1, y0, y1] = self.exit_pupil[ring]?;
+
+        // The bounds were found for points along `+x`, so they're turned around to face this one
+        let (x, y) = (rng.gen_range(x0..x1), rng.gen_range(y0..y1));
+        let (sin, cos) = if radius > 0.0 {
+            (film.y() / radius, film.x() / radius)
+        } else {
+            (0.0, 1.0)
+        };
+        let rear = Point3::new(
+            x * cos - y * sin,
+            x * sin + y * cos,
+            self.lens.rear_element().thickness,
+        );
+        let direction = (rear - film).unit_vector();
+
+        // Rays are spread over the pupil, so less light gets through where it's smaller, and light arriving at an
+        // angle is spread over more of the film (the cos⁴ law)
+        let weight = direction.z().powi(4) * (x1 - x0) * (y1 - y0) / self.pupil_area;
+
+        if rng.gen::<f64>() >= weight {
+            return None;
+        }
+
+        let ray = self.lens.trace(
+            Ray {
+                wavelength,
+                ..Ray::new(film, direction)
+            },
+            true,
+        )?;
+        let to_world = |a: Vector3| a.x() * self.u + a.y() * self.v - a.z() * self.w;
+
+        Some(Ray {
+            origin: self.origin + self.millimetre * to_world(ray.origin),
+            direction: to_world(ray.direction),
+            time: shutter_time(self.shutter_open, self.shutter_close, rng),
+            wavelength,
+            dispersed: self.lens.is_dispersive(),
+        })
+    }
+}
+
+/// How a [`StereoCamera`] fits the images for both eyes into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// The left eye's image on the left and the right eye's on the right.
+    #[default]
+    SideBySide,
+    /// The left eye's image above the right eye's.
+    OverUnder,
+}
+
+/// A pair of cameras a viewer's eyes apart, rendering both views into one image for VR headsets and 3D displays.
+pub struct StereoCamera {
+    pub left: Box<dyn Camera>,
+    pub right: Box<dyn Camera>,
+    pub layout: StereoLayout,
+}
+
+impl StereoCamera {
+    pub fn new(left: Box<dyn Camera>, right: Box<dyn Camera>, layout: StereoLayout) -> Self {
+        StereoCamera {
+            left,
+            right,
+            layout,
+        }
+    }
+
+    /// Two copies of `camera` `interpupillary_distance` apart, which see things `convergence` away in the same
+    /// place. Closer things appear to stand out of the screen and further things to recede into it.
+    pub fn perspective(
+        camera: PerspectiveCamera,
+        interpupillary_distance: f64,
+        convergence: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let offset = interpupillary_distance / 2.0;
+
+        Self::new(
+            Box::new(camera.eye(-offset, convergence)),
+            Box::new(camera.eye(offset, convergence)),
+            layout,
+        )
+    }
+
+    /// Omni-directional stereo, which gives a 360 degree panorama depth in every direction the viewer looks. Pass
+    /// infinity as `convergence` to keep each pair of rays parallel.
+    pub fn omnidirectional(
+        camera: PanoramicCamera,
+        interpupillary_distance: f64,
+        convergence: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let offset = interpupillary_distance / 2.0;
+
+        Self::new(
+            Box::new(camera.eye(-offset, convergence)),
+            Box::new(camera.eye(offset, convergence)),
+            layout,
+        )
+    }
+
+    /// The eye that sees the point `(s, t)` of the image, and where that point is in the eye's own image.
+    fn eye(&self, s: f64, t: f64) -> (&dyn Camera, f64, f64) {
+        match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => (self.left.as_ref(), 2.0 * s, t),
+            StereoLayout::SideBySide => (self.right.as_ref(), 2.0 * s - 1.0, t),
+            StereoLayout::OverUnder if t >= 0.5 => (self.left.as_ref(), s, 2.0 * t - 1.0),
+            StereoLayout::OverUnder => (self.right.as_ref(), s, 2.0 * t),
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn ray(&self, s: f64, t: f64, rng: &mut ThreadRng) -> Option<Ray> {
+        let (eye, s, t) = self.eye(s, t);
+
+        eye.ray(s, t, rng)
+    }
+
+    fn ray_at_wavelength(
+        &self,
+        s: f64,
+        t: f64,
+        wavelength: f64,
+        rng: &mut ThreadRng,
+    ) -> Option<Ray> {
+        let (eye, s, t) = self.eye(s, t);
+
+        eye.ray_at_wavelength(s, t, wavelength, rng)
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        match self.layout {
+            StereoLayout::SideBySide => 2.0 * self.left.aspect_ratio(),
+            StereoLayout::OverUnder => self.left.aspect_ratio() / 2.0,
+        }
+    }
+
+    fn exposure(&self) -> f64 {
+        self.left.exposure()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::{rngs::ThreadRng, thread_rng};
+
+    use crate::{
+        camera::{
+            equirectangular_coordinates, Aperture, Camera, Exposure, FieldOfView,
+            OrthographicCamera, PanoramicCamera, PerspectiveCamera, Projection, RealisticCamera,
+            StereoCamera, StereoLayout,
+        },
+        hittables::{HittableList, Sphere},
+        lens::LensSystem,
+        materials::Lambertian,
+        math::{Color, Point3, Ray, Vector3},
+    };
+
+    #[test]
+    fn orthographic_rays_are_parallel_and_span_the_view() {
+        let camera = OrthographicCamera::new(
+            Point3::new(0.0, 0.0, 10.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            4.0,
+            2.0,
+        );
+        let mut rng = thread_rng();
+
+        let lower_left = camera.ray(0.0, 0.0, &mut rng).unwrap();
+        let upper_right = camera.ray(1.0, 1.0, &mut rng).unwrap();
+
+        assert_eq!(camera.aspect_ratio(), 2.0);
+        assert_eq!(lower_left.direction, upper_right.direction);
+        assert!((lower_left.origin - Point3::new(-2.0, -1.0, 10.0)).near_zero());
+        assert!((upper_right.origin - Point3::new(2.0, 1.0, 10.0)).near_zero());
+    }
+
+    #[test]
+    fn equirectangular_camera_matches_environment_lookup() {
+        let camera = PanoramicCamera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Projection::full_sphere(),
+        );
+        let mut rng = thread_rng();
+
+        for (s, t) in [(0.5, 0.5), (0.1, 0.3), (0.8, 0.9), (0.3, 0.05)] {
+            let ray = camera.ray(s, t, &mut rng).unwrap();
+            let (u, v) = equirectangular_coordinates(ray.direction);
+
+            assert!(
+                (u - s).abs() < 1e-9 && (v - t).abs() < 1e-9,
+                "{s} {t} {u} {v}"
+            );
+        }
+    }
+
+    #[test]
+    fn fisheye_edge_is_half_the_field_of_view() {
+        for projection in [
+            Projection::EquidistantFisheye { fov: 180.0 },
+            Projection::EquisolidFisheye { fov: 180.0 },
+        ] {
+            let camera = PanoramicCamera::new(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 0.0, -1.0),
+                Vector3::new(0.0, 1.0, 0.0),
+                projection,
+            );
+            let mut rng = thread_rng();
+
+            let edge = camera.ray(1.0, 0.5, &mut rng).unwrap();
+            assert!((edge.direction.unit_vector() - Vector3::new(1.0, 0.0, 0.0)).near_zero());
+            assert!(camera.ray(0.0, 0.0, &mut rng).is_none());
+        }
+    }
+
+    #[test]
+    fn stereo_eyes_converge() {
+        let lookfrom = Point3::new(1.0, 2.0, 3.0);
+        let forward = Vector3::new(0.0, 0.0, -1.0);
+        let mut rng = thread_rng();
+
+        let perspective = StereoCamera::perspective(
+            PerspectiveCamera::new(
+                lookfrom,
+                lookfrom + forward,
+                Vector3::new(0.0, 1.0, 0.0),
+                60.0,
+                1.0,
+                0.0,
+                2.0,
+            ),
+            0.064,
+            5.0,
+            StereoLayout::SideBySide,
+        );
+        let omnidirectional = StereoCamera::omnidirectional(
+            PanoramicCamera::new(
+                lookfrom,
+                lookfrom + forward,
+                Vector3::new(0.0, 1.0, 0.0),
+                Projection::full_sphere(),
+            ),
+            0.064,
+            5.0,
+            StereoLayout::OverUnder,
+        );
+
+        // The centres of both eyes' images, which should look at the same point
+        let target = lookfrom + 5.0 * forward;
+        let pairs = [
+            (&perspective, (0.25, 0.5), (0.75, 0.5)),
+            (&omnidirectional, (0.5, 0.75), (0.5, 0.25)),
+        ];
+
+        for (camera, left, right) in pairs {
+            let left = camera.ray(left.0, left.1, &mut rng).unwrap();
+            let right = camera.ray(right.0, right.1, &mut rng).unwrap();
+
+            assert!(((right.origin - left.origin).length() - 0.064).abs() < 1e-9);
+            assert!(right.origin.x() > left.origin.x());
+
+            for ray in [left, right] {
+                let to_target = target - ray.origin;
+                assert!(ray.direction.cross(&to_target).near_zero(), "{ray:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn polygonal_apertures_stay_within_their_blades() {
+        let mut rng = thread_rng();
+        let (blades, rotation) = (6, 15.0);
+        let aperture = Aperture::Polygon { blades, rotation };
+        let step = 2.0 * PI / blades as f64;
+        let apothem = (step / 2.0).cos();
+        let mut farthest = 0.0_f64;
+
+        for _ in 0..10_000 {
+            let (x, y) = aperture.sample(&mut rng);
+
+            for k in 0..blades {
+                let normal = rotation.to_radians() + step * (k as f64 + 0.5);
+
+                assert!(x * normal.cos() + y * normal.sin() <= apothem + 1e-9);
+            }
+
+            farthest = farthest.max((x * x + y * y).sqrt());
+        }
+
+        // The samples reach out into the corners
+        assert!(farthest > 0.95, "{farthest}");
+    }
+
+    #[test]
+    fn cat_eye_only_vignettes_away_from_the_centre() {
+        let mut rng = thread_rng();
+        let camera = PerspectiveCamera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            60.0,
+            1.5,
+            0.5,
+            1.0,
+        )
+        .with_cat_eye(1.0);
+
+        let passed = |s, t, rng: &mut ThreadRng| {
+            (0..10_000)
+                .filter(|_| camera.ray(s, t, rng).is_some())
+                .count()
+        };
+
+        assert_eq!(passed(0.5, 0.5, &mut rng), 10_000);
+
+        // Two unit disks a radius apart overlap on 39% of either
+        let corner = passed(1.0, 1.0, &mut rng) as f64 / 10_000.0;
+        assert!((corner - 0.391).abs() < 0.02, "{corner}");
+    }
+
+    #[test]
+    fn realistic_camera_focuses_on_its_target() {
+        let mut rng = thread_rng();
+        let lookfrom = Point3::new(1.0, 2.0, 3.0);
+        let lookat = Point3::new(-1.0, 1.0, -2.0);
+        let camera = RealisticCamera::new(
+            lookfrom,
+            lookat,
+            Vector3::new(0.0, 1.0, 0.0),
+            LensSystem::double_gauss(),
+            43.3,
+            1.5,
+            0.01,
+        );
+
+        // Every ray through the centre of the image passes through the target
+        let mut traced = 0;
+
+        for _ in 0..1000 {
+            let Some(ray) = camera.ray(0.5, 0.5, &mut rng) else {
+                continue;
+            };
+            let t = (lookat - ray.origin).dot(&ray.direction) / ray.direction.length_squared();
+
+            assert!((ray.at(t) - lookat).length() < 0.01, "{:?}", ray.at(t));
+            traced += 1;
+        }
+
+        assert!(traced > 500, "{traced}");
+    }
+
+    #[test]
+    fn exposure_trades_shutter_speed_for_aperture() {
+        // Each of these is a stop brighter than f/1 for a second at ISO 100, which is EV 0
+        let base = Exposure::new(100.0, 1.0, 1.0);
+        assert_eq!(base.ev100(), 0.0);
+
+        for exposure in [
+            Exposure::new(100.0, 2.0, 1.0),
+            Exposure::new(200.0, 1.0, 1.0),
+            Exposure::new(100.0, 1.0, 1.0 / 2.0_f64.sqrt()),
+            Exposure::new(100.0, 4.0, 2.0_f64.sqrt()),
+        ] {
+            assert!((exposure.scale() - 2.0 * base.scale()).abs() < 1e-12);
+        }
+
+        // A 35mm lens at f/2.8 is 12.5mm across
+        let camera = PerspectiveCamera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            2.0 * (12.0_f64 / 35.0).atan().to_degrees(),
+            1.5,
+            0.0,
+            5.0,
+        )
+        .with_exposure(Exposure::new(100.0, 1.0 / 60.0, 2.8), 0.001);
+        assert!((2.0 * camera.lens_radius - 0.0125).abs() < 1e-6);
+    }
+
+    #[test]
+    fn field_of_view_modes_frame_the_same_view() {
+        let mut rng = thread_rng();
+        let aspect_ratio = 1.5;
+        // A 50mm lens on a 36 by 24mm sensor
+        let half_height: f64 = 12.0 / 50.0;
+        let modes = [
+            FieldOfView::Vertical(2.0 * half_height.atan().to_degrees()),
+            FieldOfView::Horizontal(2.0 * (half_height * aspect_ratio).atan().to_degrees()),
+            FieldOfView::Diagonal(2.0 * (half_height * 1.5_f64.hypot(1.0)).atan().to_degrees()),
+            FieldOfView::FocalLength {
+                focal_length: 50.0,
+                sensor_height: 24.0,
+            },
+        ];
+        let mut corner = |mode| {
+            let camera = PerspectiveCamera::builder()
+                .look_from(Point3::new(1.0, 2.0, 3.0))
+                .look_at(Point3::new(0.0, 0.0, 0.0))
+                .field_of_view(mode)
+                .aspect_ratio(aspect_ratio)
+                .build();
+
+            camera
+                .ray(1.0, 1.0, &mut rng)
+                .unwrap()
+                .direction
+                .unit_vector()
+        };
+
+        let expected = corner(modes[0]);
+
+        for mode in modes {
+            assert!((corner(mode) - expected).near_zero(), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn roll_tilts_the_horizon_and_autofocus_finds_the_subject() {
+        let mut rng = thread_rng();
+        let mut world = HittableList::new();
+        world.add(Sphere::new(
+            Point3::new(0.0, 0.0, -5.0),
+            1.0,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        ));
+
+        let camera = PerspectiveCamera::builder()
+            .field_of_view(FieldOfView::Vertical(60.0))
+            .aspect_ratio(1.0)
+            .roll(90.0)
+            .aperture(0.5)
+            .focus_distance(100.0)
+            .build()
+            .autofocus(&world, 0.5, 0.5);
+
+        // Rolled a quarter turn clockwise, the top of the image looks to the right
+        let in_focus = |ray: Ray| ray.at((-4.0 - ray.origin.z()) / ray.direction.z());
+        let top = in_focus(camera.ray(0.5, 1.0, &mut rng).unwrap());
+        assert!(top.x() > 0.0 && top.y().abs() < 1e-9, "{top:?}");
+
+        // Every ray through the centre meets at the front of the sphere
+        for _ in 0..100 {
+            let point = in_focus(camera.ray(0.5, 0.5, &mut rng).unwrap());
+
+            assert!(
+                point.x().abs() < 1e-9 && point.y().abs() < 1e-9,
+                "{point:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn shift_keeps_verticals_vertical() {
+        let mut rng = thread_rng();
+        let camera = PerspectiveCamera::builder()
+            .look_at(Point3::new(1.0, 0.0, -3.0))
+            .shift(0.1, 0.4)
+            .build();
+
+        // Rays down a column of the image all lie in an upright plane through the camera
+        for s in [0.0, 0.3, 1.0] {
+            let top = camera.ray(s, 1.0, &mut rng).unwrap().direction;
+            let bottom = camera.ray(s, 0.0, &mut rng).unwrap().direction;
+
+            assert!(top.cross(&bottom).y().abs() < 1e-9);
+        }
+
+        // The view is raised, but the camera still looks level
+        assert!(camera.ray(0.5, 0.5, &mut rng).unwrap().direction.y() > 0.0);
+    }
+
+    #[test]
+    fn tilted_lens_focuses_on_a_tilted_plane() {
+        let mut rng = thread_rng();
+        let camera = PerspectiveCamera::builder()
+            .look_from(Point3::new(0.0, 2.0, 0.0))
+            .look_at(Point3::new(0.0, 0.0, -2.0))
+            .aperture(0.5)
+            .tilt(45.0, 0.0)
+            .build();
+
+        // The camera looks 45 degrees down, so the plane in focus lies along the ground
+        for (s, t) in [(0.5, 0.5), (0.2, 0.9), (0.9, 0.1), (0.0, 0.6)] {
+            let on_ground = |ray: Ray| ray.at(-ray.origin.y() / ray.direction.y());
+            let expected = on_ground(camera.ray(s, t, &mut rng).unwrap());
+
+            for _ in 0..20 {
+                let point = on_ground(camera.ray(s, t, &mut rng).unwrap());
+
+                assert!((point - expected).length() < 1e-9, "{point:?} {expected:?}");
+            }
+        }
     }
 }
@@ -0,0 +1,116 @@
+use rand::{rngs::ThreadRng, Rng};
+
+use crate::math::{Color, Ray};
+
+/// A scene-wide participating medium that every ray travels through, either uniform or thinning out with height.
+///
+/// Density decays as `density * exp(-height_falloff * (y - base_height))`, so a `height_falloff` of 0 gives
+/// uniform fog, and larger values give thin mist that hugs the ground below `base_height`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// The fraction of light scattered (rather than absorbed) at each collision with the fog.
+    pub color: Color,
+    pub density: f64,
+    pub height_falloff: f64,
+    pub base_height: f64,
+    /// The Henyey–Greenstein anisotropy of the fog particles; 0 scatters equally in all directions.
+    pub g: f64,
+}
+
+impl Fog {
+    pub fn homogeneous(color: Color, density: f64) -> Self {
+        Self {
+            color,
+            density,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            g: 0.0,
+        }
+    }
+
+    pub fn height(color: Color, density: f64, height_falloff: f64, base_height: f64) -> Self {
+        Self {
+            color,
+            density,
+            height_falloff,
+            base_height,
+            g: 0.0,
+        }
+    }
+
+    /// Samples where along `ray` light first collides with the fog, returning `None` if it gets past `t_max`.
+    pub fn sample_collision(&self, ray: Ray, t_max: f64, rng: &mut ThreadRng) -> Option<f64> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let dy = ray.direction.y() / ray_length;
+        let optical_depth = -(1.0 - rng.gen::<f64>()).ln();
+
+        // Density at the ray's origin, and how quickly it changes per unit travelled
+        let k = self.height_falloff;
+        let start_density = self.density * (-k * (ray.origin.y() - self.base_height)).exp();
+        let rate = k * dy;
+
+        // Invert the optical depth integral start_density * (1 - exp(-rate * s)) / rate
+        let distance = if rate.abs() < 1e-9 {
+            optical_depth / start_density
+        } else {
+            let remaining = 1.0 - optical_depth * rate / start_density;
+
+            // Heading up into fog that thins out too fast to ever reach the sampled depth
+            if remaining <= 0.0 {
+                return None;
+            }
+
+            -remaining.ln() / rate
+        };
+
+        let t = distance / ray_length;
+
+        (t < t_max).then_some(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use crate::{
+        fog::Fog,
+        math::{Color, Point3, Ray, Vector3},
+    };
+
+    fn escape_fraction(fog: Fog, ray: Ray, t_max: f64) -> f64 {
+        let mut rng = thread_rng();
+        let samples = 20000;
+        let escaped = (0..samples)
+            .filter(|_| fog.sample_collision(ray, t_max, &mut rng).is_none())
+            .count();
+
+        escaped as f64 / samples as f64
+    }
+
+    #[test]
+    fn homogeneous_fog_follows_beer_lambert() {
+        let fog = Fog::homogeneous(Color::new(1.0, 1.0, 1.0), 0.5);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+
+        // t_max of 1 is 2 units of distance along this ray
+        let fraction = escape_fraction(fog, ray, 1.0);
+
+        assert!((fraction - (-1.0f64).exp()).abs() < 0.02, "{fraction}");
+    }
+
+    #[test]
+    fn height_fog_follows_optical_depth() {
+        let fog = Fog::height(Color::new(1.0, 1.0, 1.0), 0.8, 1.0, 0.0);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+
+        // Straight up to infinity, the optical depth is density / falloff
+        let fraction = escape_fraction(fog, ray, f64::INFINITY);
+
+        assert!((fraction - (-0.8f64).exp()).abs() < 0.02, "{fraction}");
+    }
+}
@@ -1,14 +1,30 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use rand::{thread_rng, Rng};
+
 use crate::{
+    aabb::Aabb,
+    camera::equirectangular_coordinates,
+    fog::Fog,
+    image::Image,
     materials::Material,
-    math::{Point3, Ray, Vector3},
+    math::{Color, Onb, Point3, Ray, Vector3},
+    transform::{AnimatedTransform, Transform},
 };
 
+#[derive(Clone, Copy)]
 pub struct HitRecord<'a> {
     pub point: Point3,
     pub normal: Vector3,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
+    /// How the point moves as `u` and `v` increase, which orients textures that bend the normal. Zero for surfaces
+    /// without a parameterization.
+    pub dpdu: Vector3,
+    pub dpdv: Vector3,
     pub front_face: bool,
-    pub material: &'a Box<dyn Material>,
+    pub material: &'a dyn Material,
 }
 
 impl<'a> HitRecord<'a> {
@@ -17,17 +33,40 @@ impl<'a> HitRecord<'a> {
         normal: Vector3,
         t: f64,
         front_face: bool,
-        material: &'a Box<dyn Material>,
+        material: &'a dyn Material,
     ) -> Self {
         Self {
             point,
             normal,
             t,
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vector3::new(0.0, 0.0, 0.0),
+            dpdv: Vector3::new(0.0, 0.0, 0.0),
             front_face,
             material,
         }
     }
 
+    /// The shading frame at the hit: `u` follows the tangent `dpdu`, `v` the bitangent and `w` the normal.
+    ///
+    /// Falls back to an arbitrary frame around the normal when the surface has no tangent.
+    pub fn tangent_frame(&self) -> Onb {
+        let tangent = self.dpdu - self.normal * self.normal.dot(&self.dpdu);
+
+        if tangent.near_zero() {
+            return Onb::new(self.normal);
+        }
+
+        let u = tangent.unit_vector();
+
+        Onb {
+            u,
+            v: Vector3::cross(&self.normal, &u),
+            w: self.normal,
+        }
+    }
+
     pub fn set_face_normal(&mut self, ray: Ray, outward_normal: Vector3) {
         self.front_face = Vector3::dot(&ray.direction, &outward_normal) < 0.0;
         self.normal = if self.front_face {
@@ -39,13 +78,20 @@ impl<'a> HitRecord<'a> {
 }
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+
+    /// Returns a box enclosing the object at every instant between `time0` and `time1`, or `None` if it is
+    /// unbounded.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
 }
 
 pub struct Sphere {
+    /// The center at time zero.
     pub center: Point3,
     pub radius: f64,
     pub material: Box<dyn Material>,
+    /// How far the center moves per unit of time.
+    pub velocity: Vector3,
 }
 
 impl Sphere {
@@ -54,13 +100,68 @@ impl Sphere {
             center,
             radius,
             material,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
         })
     }
+
+    /// A sphere moving in a straight line from `center0` at `time0` to `center1` at `time1`. If both times are the
+    /// same, the sphere stays at `center0`.
+    pub fn moving(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Box<dyn Material>,
+    ) -> Box<Self> {
+        let velocity = if time1 == time0 {
+            Vector3::new(0.0, 0.0, 0.0)
+        } else {
+            (center1 - center0) / (time1 - time0)
+        };
+
+        Box::new(Self {
+            center: center0 - velocity * time0,
+            radius,
+            material,
+            velocity,
+        })
+    }
+
+    pub fn center_at(&self, time: f64) -> Point3 {
+        self.center + self.velocity * time
+    }
+
+    /// Maps a point on the unit sphere to texture coordinates, with `v` running from the bottom pole to the top.
+    fn uv(point: Point3) -> (f64, f64) {
+        let theta = (-point.y()).acos();
+        let phi = (-point.z()).atan2(point.x()) + PI;
+
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// The partial derivatives of the point with respect to the `uv` coordinates from [`Sphere::uv`].
+    fn derivatives(point: Point3, radius: f64) -> (Vector3, Vector3) {
+        let Vector3(x, y, z) = point;
+        let sin_theta = (1.0 - y * y).max(0.0).sqrt();
+
+        let dpdu = 2.0 * PI * radius * Vector3::new(z, 0.0, -x);
+
+        // At the poles the `v` direction depends on which way you leave them, so follow `u` round instead
+        let dpdv = if sin_theta > 1e-6 {
+            PI * radius * Vector3::new(-y * x / sin_theta, sin_theta, -y * z / sin_theta)
+        } else {
+            Vector3::new(0.0, 0.0, 0.0)
+        };
+
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let oc = ray.origin - self.center;
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let center = self.center_at(ray.time);
+        let oc = ray.origin - center;
         let a = ray.direction.length_squared();
         let half_b = Vector3::dot(&oc, &ray.direction);
         let c = oc.length_squared() - self.radius * self.radius;
@@ -73,37 +174,469 @@ impl Hittable for Sphere {
 
         let sqrt_discriminant = discriminant.sqrt();
 
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-half_b - sqrt_discriminant) / a;
-        if root < t_min || t_max < root {
-            root = (-half_b + sqrt_discriminant) / a;
+        // Take the nearest root that lies in the acceptable range, unless the surface is cut out there
+        for root in [
+            (-half_b - sqrt_discriminant) / a,
+            (-half_b + sqrt_discriminant) / a,
+        ] {
             if root < t_min || t_max < root {
-                return None;
+                continue;
+            }
+
+            let mut hit_record = HitRecord::new(
+                ray.at(root),
+                Vector3::new(0.0, 0.0, 0.0),
+                root,
+                false,
+                self.material.as_ref(),
+            );
+
+            let outward_normal = (hit_record.point - center) / self.radius;
+            hit_record.set_face_normal(ray, outward_normal);
+            (hit_record.u, hit_record.v) = Sphere::uv(outward_normal);
+            (hit_record.dpdu, hit_record.dpdv) = Sphere::derivatives(outward_normal, self.radius);
+
+            if !self.material.is_cut_out(hit_record) {
+                return Some(hit_record);
             }
         }
 
-        let mut hit_record = HitRecord::new(
-            ray.at(root),
-            Vector3::new(0.0, 0.0, 0.0),
-            root,
-            false,
-            &self.material,
-        );
+        None
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let radius = Vector3::new(self.radius, self.radius, self.radius);
+        let (center0, center1) = (self.center_at(time0), self.center_at(time1));
+
+        Some(Aabb::surrounding(
+            Aabb::new(center0 - radius, center0 + radius),
+            Aabb::new(center1 - radius, center1 + radius),
+        ))
+    }
+}
+
+/// A parallelogram with corner `q` and edges `u` and `v`.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vector3,
+    pub v: Vector3,
+    pub material: Box<dyn Material>,
+    normal: Vector3,
+    d: f64,
+    w: Vector3,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vector3, v: Vector3, material: Box<dyn Material>) -> Box<Self> {
+        let n = Vector3::cross(&u, &v);
+        let normal = n.unit_vector();
+
+        Box::new(Self {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d: normal.dot(&q),
+            w: n / n.dot(&n),
+        })
+    }
+}
 
-        let outward_normal = (hit_record.point - self.center) / self.radius;
-        hit_record.set_face_normal(ray, outward_normal);
+impl Hittable for Quad {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denom = self.normal.dot(&ray.direction);
+
+        // The ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(&ray.origin)) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let planar = point - self.q;
+        let alpha = self.w.dot(&Vector3::cross(&planar, &self.v));
+        let beta = self.w.dot(&Vector3::cross(&self.u, &planar));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut hit_record = HitRecord::new(point, self.normal, t, false, self.material.as_ref());
+        hit_record.set_face_normal(ray, self.normal);
+        (hit_record.u, hit_record.v) = (alpha, beta);
+        (hit_record.dpdu, hit_record.dpdv) = (self.u, self.v);
+
+        if self.material.is_cut_out(hit_record) {
+            return None;
+        }
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        let diagonal_a = Aabb::new(self.q, self.q + self.u + self.v);
+        let diagonal_b = Aabb::new(self.q + self.u, self.q + self.v);
+
+        Some(Aabb::surrounding(diagonal_a, diagonal_b).padded())
+    }
+}
+
+/// Builds the six quads of the box spanned by two opposite corners.
+pub fn make_box(a: Point3, b: Point3, material: Arc<dyn Material>) -> HittableList {
+    let mut sides = HittableList::new();
+
+    let min = a.min(&b);
+    let max = a.max(&b);
+
+    let dx = Vector3::new(max.x() - min.x(), 0.0, 0.0);
+    let dy = Vector3::new(0.0, max.y() - min.y(), 0.0);
+    let dz = Vector3::new(0.0, 0.0, max.z() - min.z());
+
+    let faces = [
+        (Point3::new(min.x(), min.y(), max.z()), dx, dy), // front
+        (Point3::new(max.x(), min.y(), max.z()), -dz, dy), // right
+        (Point3::new(max.x(), min.y(), min.z()), -dx, dy), // back
+        (Point3::new(min.x(), min.y(), min.z()), dz, dy), // left
+        (Point3::new(min.x(), max.y(), max.z()), dx, -dz), // top
+        (Point3::new(min.x(), min.y(), min.z()), dx, dz), // bottom
+    ];
+
+    for (q, u, v) in faces {
+        sides.add(Quad::new(q, u, v, Box::new(material.clone())));
+    }
+
+    sides
+}
+
+/// Places a shared object into the world with a transform, so one model can appear many times.
+pub struct Instance {
+    pub object: Arc<dyn Hittable>,
+    pub transform: Transform,
+    inverse: Transform,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Box<Self> {
+        Box::new(Self {
+            object,
+            transform,
+            inverse: transform.inverse(),
+        })
+    }
+}
+
+/// Intersects `object` in its own space, where `to_world` places it in the world.
+fn hit_transformed<'a>(
+    object: &'a dyn Hittable,
+    to_world: &Transform,
+    to_object: &Transform,
+    ray: Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // The direction isn't renormalized, so `t` carries over unchanged.
+    let local_ray = Ray {
+        origin: to_object.point(ray.origin),
+        direction: to_object.vector(ray.direction),
+        ..ray
+    };
+
+    let mut hit_record = object.hit(local_ray, t_min, t_max)?;
+    hit_record.point = to_world.point(hit_record.point);
+    hit_record.normal = to_world.normal(hit_record.normal).unit_vector();
+    hit_record.dpdu = to_world.vector(hit_record.dpdu);
+    hit_record.dpdv = to_world.vector(hit_record.dpdv);
+
+    Some(hit_record)
+}
+
+fn transformed_box(bbox: Aabb, transform: &Transform) -> Aabb {
+    let corners = bbox.corners().map(|corner| transform.point(corner));
+
+    corners[1..]
+        .iter()
+        .fold(Aabb::new(corners[0], corners[0]), |acc, &corner| {
+            Aabb::surrounding(acc, Aabb::new(corner, corner))
+        })
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_transformed(
+            self.object.as_ref(),
+            &self.transform,
+            &self.inverse,
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.object
+            .bounding_box(time0, time1)
+            .map(|bbox| transformed_box(bbox, &self.transform))
+    }
+}
+
+/// Like [`Instance`], but with a transform that changes over time, e.g. a spinning or moving object.
+pub struct AnimatedInstance {
+    pub object: Arc<dyn Hittable>,
+    pub transform: AnimatedTransform,
+}
+
+impl AnimatedInstance {
+    pub fn new(object: Arc<dyn Hittable>, transform: AnimatedTransform) -> Box<Self> {
+        Box::new(Self { object, transform })
+    }
+}
+
+impl Hittable for AnimatedInstance {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let transform = self.transform.at(ray.time);
+
+        hit_transformed(
+            self.object.as_ref(),
+            &transform,
+            &transform.inverse(),
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let bbox = self.object.bounding_box(time0, time1)?;
+
+        // Rotation sweeps corners along arcs, so union the box at many instants and then pad by how far a corner
+        // can stray from the straight path between two consecutive instants.
+        let steps = 64;
+        let mut corners = bbox
+            .corners()
+            .map(|corner| self.transform.at(time0).point(corner));
+        let mut swept = transformed_box(bbox, &self.transform.at(time0));
+        let mut max_step: f64 = 0.0;
+
+        for i in 1..=steps {
+            let transform = self
+                .transform
+                .at(time0 + (time1 - time0) * i as f64 / steps as f64);
+            let next = bbox.corners().map(|corner| transform.point(corner));
+
+            for (a, b) in corners.iter().zip(next.iter()) {
+                max_step = max_step.max((*b - *a).length());
+            }
+
+            swept = Aabb::surrounding(swept, transformed_box(bbox, &transform));
+            corners = next;
+        }
+
+        let pad = Vector3::new(max_step, max_step, max_step);
+
+        Some(Aabb::new(swept.minimum - pad, swept.maximum + pad))
+    }
+}
+
+/// A cloud of particles of constant density, like smoke or fog, filling the inside of a convex `boundary`.
+///
+/// Rays may start inside the medium (after scattering in it, or bouncing off an object inside it), so the boundary
+/// is intersected along the whole line and then clipped to the ray's interval.
+pub struct ConstantMedium {
+    pub boundary: Box<dyn Hittable>,
+    pub density: f64,
+    pub phase_function: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase_function: Box<dyn Material>,
+    ) -> Box<Self> {
+        Box::new(Self {
+            boundary,
+            density,
+            phase_function,
+        })
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let entry = self.boundary.hit(ray, f64::NEG_INFINITY, f64::INFINITY)?;
+        let exit = self.boundary.hit(ray, entry.t + 0.0001, f64::INFINITY)?;
+
+        let t_enter = entry.t.max(t_min);
+        let t_exit = exit.t.min(t_max);
+
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = -(1.0 - thread_rng().gen::<f64>()).ln() / self.density;
+
+        // The ray made it through without hitting a particle
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+
+        // The normal and face are meaningless inside a volume, so they're set arbitrarily
+        Some(HitRecord::new(
+            ray.at(t),
+            Vector3::new(1.0, 0.0, 0.0),
+            t,
+            true,
+            self.phase_function.as_ref(),
+        ))
+    }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.boundary.bounding_box(time0, time1)
+    }
+}
+
+/// A bounding volume hierarchy, which lets a ray skip whole groups of objects it can't hit.
+pub struct BvhNode {
+    left: Box<dyn Hittable>,
+    right: Option<Box<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    /// Builds a hierarchy over `objects`, which must be non-empty and all bounded between `time0` and `time1`.
+    pub fn new(mut objects: Vec<Box<dyn Hittable>>, time0: f64, time1: f64) -> Box<Self> {
+        let boxes: Vec<Aabb> = objects
+            .iter()
+            .map(|object| {
+                object
+                    .bounding_box(time0, time1)
+                    .expect("BvhNode can only contain bounded objects")
+            })
+            .collect();
+
+        let bbox = boxes[1..]
+            .iter()
+            .fold(boxes[0], |acc, &bbox| Aabb::surrounding(acc, bbox));
+
+        if objects.len() == 1 {
+            return Box::new(Self {
+                left: objects.remove(0),
+                right: None,
+                bbox,
+            });
+        }
+
+        // Split at the median centroid along the axis where the centroids are most spread out.
+        let centroids = boxes.iter().skip(1).fold(
+            Aabb::new(boxes[0].centroid(), boxes[0].centroid()),
+            |acc, bbox| Aabb::surrounding(acc, Aabb::new(bbox.centroid(), bbox.centroid())),
+        );
+        let axis = centroids.longest_axis();
+
+        let mut keyed: Vec<(f64, Box<dyn Hittable>)> = boxes
+            .iter()
+            .map(|bbox| bbox.centroid()[axis])
+            .zip(objects)
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut objects: Vec<Box<dyn Hittable>> = keyed.into_iter().map(|(_, o)| o).collect();
+        let upper = objects.split_off(objects.len() / 2);
+
+        let left: Box<dyn Hittable> = BvhNode::new(objects, time0, time1);
+        let right: Box<dyn Hittable> = BvhNode::new(upper, time0, time1);
+
+        Box::new(Self {
+            left,
+            right: Some(right),
+            bbox,
+        })
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
+        }
+
+        let left = self.left.hit(ray, t_min, t_max);
+        let closest = left.map_or(t_max, |rec| rec.t);
+        let right = self
+            .right
+            .as_ref()
+            .and_then(|right| right.hit(ray, t_min, closest));
+
+        right.or(left)
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+/// What a ray sees when it escapes the scene without hitting anything.
+#[derive(Clone)]
+pub enum Background {
+    /// A white-to-blue vertical gradient.
+    Sky,
+    Solid(Color),
+    /// A full-sphere equirectangular image with `-z` in its centre and `y` up, such as one rendered by a
+    /// [`PanoramicCamera`](crate::camera::PanoramicCamera) with
+    /// [`Projection::full_sphere`](crate::camera::Projection::full_sphere).
+    Environment(Arc<Image>),
+}
+
+impl Background {
+    pub fn color(&self, ray: Ray) -> Color {
+        match self {
+            Background::Sky => {
+                let unit_direction = ray.direction.unit_vector();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+
+                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + Color::new(0.5, 0.7, 1.0) * t
+            }
+            Background::Solid(color) => *color,
+            Background::Environment(image) => {
+                let (s, t) = equirectangular_coordinates(ray.direction);
+                let x = ((s * image.width as f64) as u32).min(image.width - 1);
+                let y = (((1.0 - t) * image.height as f64) as u32).min(image.height - 1);
+
+                // Images are stored from the top row down
+                image.pixels[(y * image.width + x) as usize]
+            }
+        }
+    }
 }
 
 pub struct HittableList {
     pub objects: Vec<Box<dyn Hittable>>,
+    pub background: Background,
+    pub fog: Option<Fog>,
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HittableList {
     pub fn new() -> Self {
-        Self { objects: vec![] }
+        Self {
+            objects: vec![],
+            background: Background::Sky,
+            fog: None,
+        }
     }
 
     pub fn add(&mut self, object: Box<dyn Hittable>) {
@@ -113,8 +646,10 @@ impl HittableList {
     pub fn clear(&mut self) {
         self.objects.clear();
     }
+}
 
-    pub fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+impl Hittable for HittableList {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut hit_anything = None;
         let mut closest_so_far = t_max;
 
@@ -127,4 +662,208 @@ impl HittableList {
 
         hit_anything
     }
+
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        self.objects
+            .iter()
+            .map(|object| object.bounding_box(time0, time1))
+            .reduce(|acc, bbox| Some(Aabb::surrounding(acc?, bbox?)))
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::thread_rng;
+
+    use crate::{
+        hittables::{make_box, ConstantMedium, Hittable, Quad, Sphere},
+        materials::{AlphaMask, CoatedMaterial, Isotropic, Lambertian, Material, MixMaterial},
+        math::{Color, Point3, Ray, Vector3},
+        textures::{SolidColor, UvChecker},
+    };
+
+    /// Grey, with holes in alternate squares of a 2 by 2 checkerboard over the texture coordinates.
+    fn checker_cutout() -> Box<dyn Material> {
+        AlphaMask::cutout(
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+            UvChecker::new(2, Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0)),
+            0.5,
+        )
+    }
+
+    #[test]
+    fn sphere_derivatives_follow_uv() {
+        let sphere = Sphere::new(
+            Point3::new(1.0, 2.0, 3.0),
+            2.0,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        );
+        let direction = Vector3::new(-0.1, -0.15, -1.0);
+        let ray = Ray::new(Point3::new(1.5, 3.0, 10.0), direction);
+        let rec = sphere.hit(ray, 0.001, f64::INFINITY).unwrap();
+
+        // Nudge the ray slightly, and check the derivatives predict where the hit point moves
+        let shifted = sphere
+            .hit(
+                Ray::new(ray.origin + rec.dpdu * 1e-5, direction),
+                0.001,
+                f64::INFINITY,
+            )
+            .unwrap();
+        let moved = shifted.point - rec.point;
+        let expected = rec.dpdu * (shifted.u - rec.u) + rec.dpdv * (shifted.v - rec.v);
+
+        assert!(
+            (moved - expected).length() < 1e-3 * moved.length(),
+            "{moved:?} {expected:?}"
+        );
+
+        let frame = rec.tangent_frame();
+        assert!(frame.u.dot(&frame.w).abs() < 1e-9 && frame.v.dot(&frame.w).abs() < 1e-9);
+        assert!(rec.dpdv.dot(&frame.v) > 0.0);
+    }
+
+    #[test]
+    fn rays_pass_through_cut_out_surfaces() {
+        let quad = Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            checker_cutout(),
+        );
+        let down = Vector3::new(0.0, 0.0, -1.0);
+
+        assert!(quad
+            .hit(
+                Ray::new(Point3::new(0.25, 0.25, 1.0), down),
+                0.001,
+                f64::INFINITY
+            )
+            .is_some());
+        assert!(quad
+            .hit(
+                Ray::new(Point3::new(0.75, 0.25, 1.0), down),
+                0.001,
+                f64::INFINITY
+            )
+            .is_none());
+
+        // The front of the sphere is cut out here, so the ray hits the inside of the back instead
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, checker_cutout());
+        let rec = sphere
+            .hit(
+                Ray::new(Point3::new(0.0, 0.1, 5.0), down),
+                0.001,
+                f64::INFINITY,
+            )
+            .unwrap();
+
+        assert!(!rec.front_face && rec.point.z() < 0.0);
+    }
+
+    #[test]
+    fn cut_outs_show_through_wrapping_materials() {
+        let is_hole = |material: Box<dyn Material>| {
+            let quad = Quad::new(
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+                material,
+            );
+            let ray = Ray::new(Point3::new(0.75, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+
+            quad.hit(ray, 0.001, f64::INFINITY).is_none()
+        };
+        let opaque = || Lambertian::new(Color::new(0.5, 0.5, 0.5));
+
+        assert!(is_hole(CoatedMaterial::new(checker_cutout(), 1.5, 0.0)));
+        assert!(is_hole(MixMaterial::new(
+            checker_cutout(),
+            checker_cutout(),
+            0.5
+        )));
+        assert!(is_hole(MixMaterial::new(checker_cutout(), opaque(), 0.0)));
+        assert!(!is_hole(MixMaterial::new(checker_cutout(), opaque(), 1.0)));
+    }
+
+    #[test]
+    fn partly_cut_out_mix_shades_only_the_visible_material() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let transparent = AlphaMask::new(
+            Lambertian::new(green),
+            SolidColor::new(Color::new(0.0, 0.0, 0.0)),
+        );
+        let quad = Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            MixMaterial::new(Lambertian::new(red), transparent, 0.5),
+        );
+        let ray = Ray::new(Point3::new(0.5, 0.5, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        let mut rng = thread_rng();
+
+        let samples = 100_000;
+        let mut passed = 0;
+        for _ in 0..samples {
+            let Some(rec) = quad.hit(ray, 0.001, f64::INFINITY) else {
+                passed += 1;
+                continue;
+            };
+            let (attenuation, _) = rec.material.scatter(ray, rec, &mut rng).unwrap();
+
+            assert_eq!(attenuation, red);
+        }
+        let fraction = passed as f64 / samples as f64;
+
+        assert!((fraction - 0.5).abs() < 0.01, "{fraction}");
+    }
+
+    #[test]
+    fn moving_sphere_with_equal_times_stays_put() {
+        let sphere = Sphere::moving(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            0.5,
+            0.5,
+            1.0,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        );
+
+        for time in [0.0, 0.5, 1.0] {
+            assert_eq!(sphere.center_at(time), Point3::new(0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn constant_medium_transmits_beer_lambert_fraction() {
+        let density = 0.7;
+        let medium = ConstantMedium::new(
+            Box::new(make_box(
+                Point3::new(-100.0, -100.0, 0.0),
+                Point3::new(100.0, 100.0, 1.0),
+                Arc::from(Lambertian::new(Color::new(0.5, 0.5, 0.5)) as Box<dyn Material>),
+            )),
+            density,
+            Isotropic::new(Color::new(1.0, 1.0, 1.0)),
+        );
+
+        // Through the whole slab from outside, and through half of it from a point inside
+        for (start, length) in [(-1.0, 1.0), (0.5, 0.5)] {
+            let ray = Ray::new(Point3::new(0.0, 0.0, start), Vector3::new(0.0, 0.0, 1.0));
+            let samples = 100_000;
+            let passed = (0..samples)
+                .filter(|_| medium.hit(ray, 0.0, f64::INFINITY).is_none())
+                .count();
+            let fraction = passed as f64 / samples as f64;
+
+            assert!(
+                (fraction - (-density * length).exp()).abs() < 0.01,
+                "{fraction}"
+            );
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use std::{
     io,
     io::Write,
-    ops::{AddAssign, Div},
+    ops::{AddAssign, Div, Mul},
 };
 
 use crate::{math::Color, utils::write_color};
@@ -39,6 +39,17 @@ impl Div<f64> for Image {
     }
 }
 
+impl Mul<f64> for Image {
+    type Output = Image;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Image {
+            pixels: self.pixels.iter().map(|&pixel| pixel * rhs).collect(),
+            ..self
+        }
+    }
+}
+
 impl Image {
     pub fn new(width: u32, height: u32) -> Image {
         Image {
@@ -0,0 +1,385 @@
+//! Systems of spherical lens elements, traced ray by ray the way light really passes through a camera lens.
+
+use std::io::{self, Read};
+
+use crate::{
+    math::{Point3, Ray, Vector3},
+    spectrum::Ior,
+};
+
+/// One surface of a lens prescription, listed from the front of the lens (facing the scene) to the back. Lengths
+/// are in millimetres.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LensElement {
+    /// The surface's radius of curvature, positive when it bulges towards the scene, or 0 for the aperture stop.
+    pub curvature_radius: f64,
+    /// The distance along the axis to the next surface, or to the film for the last one.
+    pub thickness: f64,
+    /// The index of refraction of the glass (or air) between this surface and the next.
+    pub ior: Ior,
+    /// The radius of the surface's clear opening, outside of which light is blocked.
+    pub aperture_radius: f64,
+}
+
+/// A compound lens made of several elements, with the film at `z = 0` and the scene towards `+z`.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    pub elements: Vec<LensElement>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A 50mm f/2 double-Gauss, the design behind most standard lenses, from US patent 2,673,491 scaled down from 100mm.
+/// The patent doesn't give Abbe numbers, so these are of typical glasses with the same indices.
+const DOUBLE_GAUSS: &str = "
+# radius  thickness  ior    aperture  abbe
+29.475    3.76       1.67   25.2      47.2
+84.83     0.12       1      25.2
+19.275    4.025      1.67   23        47.2
+40.77     3.275      1.699  23        30.1
+12.75     5.705      1      18
+0         4.5        1      17.1
+-14.495   1.18       1.603  17        38.0
+40.77     6.065      1.658  20        57.3
+-20.385   0.19       1      20
+437.065   3.22       1.717  20        47.9
+-39.73    0          1      20
+";
+
+impl LensSystem {
+    pub fn new(elements: Vec<LensElement>) -> Self {
+        assert!(!elements.is_empty(), "a lens needs at least one element");
+
+        Self { elements }
+    }
+
+    /// Reads a prescription table with one surface per line: its radius of curvature, thickness, index of refraction
+    /// and aperture diameter, optionally followed by an Abbe number for glass that disperses light. Indices of 0 are
+    /// read as air, and lines starting with `#` are comments. This is the format used by PBRT's lens files.
+    pub fn read_table(reader: &mut dyn Read) -> io::Result<Self> {
+        let mut table = String::new();
+        reader.read_to_string(&mut table)?;
+
+        let mut elements = Vec::new();
+
+        for line in table.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| invalid_data("lens table entries must be numbers"))?;
+
+            let (curvature_radius, thickness, nd, diameter, abbe) = match columns[..] {
+                [r, t, n, d] => (r, t, n, d, None),
+                [r, t, n, d, v] => (r, t, n, d, Some(v)),
+                _ => return Err(invalid_data("expected 4 or 5 columns per lens surface")),
+            };
+
+            let nd = if nd == 0.0 { 1.0 } else { nd };
+
+            elements.push(LensElement {
+                curvature_radius,
+                thickness,
+                ior: match abbe {
+                    Some(vd) if vd > 0.0 && nd > 1.0 => Ior::from_abbe(nd, vd),
+                    _ => Ior::Constant(nd),
+                },
+                aperture_radius: diameter / 2.0,
+            });
+        }
+
+        if elements.is_empty() {
+            return Err(invalid_data("the lens table has no surfaces"));
+        }
+
+        Ok(Self::new(elements))
+    }
+
+    /// A 50mm f/2 double-Gauss lens.
+    pub fn double_gauss() -> Self {
+        Self::read_table(&mut DOUBLE_GAUSS.as_bytes()).unwrap()
+    }
+
+    /// Whether light of different colours takes different paths through the lens (chromatic aberration).
+    pub fn is_dispersive(&self) -> bool {
+        self.elements
+            .iter()
+            .any(|element| element.ior.is_dispersive())
+    }
+
+    /// The distance from the film to the front of the lens.
+    pub fn length(&self) -> f64 {
+        self.elements.iter().map(|element| element.thickness).sum()
+    }
+
+    /// The element nearest the film, which light from the scene leaves the lens through.
+    pub fn rear_element(&self) -> &LensElement {
+        self.elements.last().unwrap()
+    }
+
+    /// Traces a ray `height` above the axis in from the scene, parallel to the axis.
+    fn trace_parallel(&self, height: f64) -> Option<Ray> {
+        let origin = Point3::new(height, 0.0, self.length() + 1.0);
+
+        self.trace(Ray::new(origin, Vector3::new(0.0, 0.0, -1.0)), false)
+    }
+
+    /// How strongly the lens bends light, as the distance at which it brings light from far away to a point.
+    pub fn focal_length(&self) -> f64 {
+        let height = self.elements[0].aperture_radius / 100.0;
+
+        self.trace_parallel(height).map_or(f64::INFINITY, |ray| {
+            height * ray.direction.z().abs() / -ray.direction.x()
+        })
+    }
+
+    /// The radius of the widest beam of light parallel to the axis that makes it through the lens, which is the
+    /// aperture stop as seen through the front of the lens (the entrance pupil).
+    pub fn entrance_pupil_radius(&self) -> f64 {
+        let (mut inside, mut outside) = (0.0, self.elements[0].aperture_radius);
+
+        for _ in 0..32 {
+            let height = (inside + outside) / 2.0;
+
+            if self.trace_parallel(height).is_some() {
+                inside = height;
+            } else {
+                outside = height;
+            }
+        }
+
+        inside
+    }
+
+    /// Resizes the aperture stop so that the lens lets in light at the given f-number, the focal length over the
+    /// diameter of the entrance pupil. It can only open up as far as the other elements allow.
+    pub fn set_f_number(&mut self, f_number: f64) {
+        let target = self.focal_length() / f_number / 2.0;
+
+        let Some(stop) = self
+            .elements
+            .iter()
+            .position(|element| element.curvature_radius == 0.0)
+        else {
+            return;
+        };
+
+        // The pupil grows in step with the stop, until some other element starts blocking the beam instead
+        for _ in 0..8 {
+            let pupil = self.entrance_pupil_radius();
+
+            if pupil == 0.0 || (pupil - target).abs() < 1e-6 * target {
+                return;
+            }
+
+            self.elements[stop].aperture_radius *= target / pupil;
+        }
+    }
+
+    /// Moves the lens along its axis so that things `distance` away from the film come into sharp focus.
+    ///
+    /// Traces a ray from a point on the axis that far away into the lens, and moves the lens until the ray meets the
+    /// axis again on the film. Moving the lens changes the distance to the point a little, so this is repeated.
+    pub fn focus(&mut self, distance: f64) {
+        for _ in 0..16 {
+            let height = self.elements[0].aperture_radius / 10.0;
+            let origin = Point3::new(0.0, 0.0, distance);
+            let direction = Vector3::new(height, 0.0, self.length() - distance);
+
+            let Some(ray) = self.trace(Ray::new(origin, direction), false) else {
+                return;
+            };
+
+            if ray.direction.x() == 0.0 {
+                return;
+            }
+
+            let image = ray.at(-ray.origin.x() / ray.direction.x()).z();
+            self.elements.last_mut().unwrap().thickness -= image;
+
+            if image.abs() < 1e-9 {
+                return;
+            }
+        }
+    }
+
+    /// Traces `ray` through every element, either outwards from the film or inwards from the scene, taking the
+    /// indices of refraction at its wavelength.
+    ///
+    /// Returns the ray leaving the last surface, or `None` if the lens barrel or the aperture stop blocked it, or it
+    /// was reflected inside the glass.
+    pub fn trace(&self, ray: Ray, from_film: bool) -> Option<Ray> {
+        let mut z = self.length();
+        let mut vertices = Vec::with_capacity(self.elements.len());
+
+        for element in &self.elements {
+            vertices.push(z);
+            z -= element.thickness;
+        }
+
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+
+        if from_film {
+            order.reverse();
+        }
+
+        let mut origin = ray.origin;
+        let mut direction = ray.direction.unit_vector();
+
+        for i in order {
+            let element = &self.elements[i];
+            let vertex = Point3::new(0.0, 0.0, vertices[i]);
+
+            let (point, normal) = if element.curvature_radius == 0.0 {
+                let t = (vertex.z() - origin.z()) / direction.z();
+
+                if t <= 0.0 || !t.is_finite() {
+                    return None;
+                }
+
+                (origin + t * direction, None)
+            } else {
+                let radius = element.curvature_radius;
+                let center = vertex - Vector3::new(0.0, 0.0, radius);
+                let oc = origin - center;
+
+                let half_b = oc.dot(&direction);
+                let discriminant = half_b * half_b - (oc.length_squared() - radius * radius);
+
+                if discriminant < 0.0 {
+                    return None;
+                }
+
+                // The surface is the side of the sphere through the vertex, which the ray reaches first when it
+                // heads towards the sphere's centre
+                let t = if (direction.z() > 0.0) != (radius > 0.0) {
+                    -half_b - discriminant.sqrt()
+                } else {
+                    -half_b + discriminant.sqrt()
+                };
+
+                if t <= 0.0 {
+                    return None;
+                }
+
+                let point = origin + t * direction;
+
+                (point, Some((point - center) / radius.abs()))
+            };
+
+            if point.x() * point.x() + point.y() * point.y()
+                > element.aperture_radius * element.aperture_radius
+            {
+                return None;
+            }
+
+            origin = point;
+
+            let Some(normal) = normal else {
+                continue;
+            };
+
+            let normal = if normal.dot(&direction) > 0.0 {
+                -normal
+            } else {
+                normal
+            };
+
+            // The medium in front of a surface is the one behind the previous surface, or air for the first
+            let film_side = element.ior.at(ray.wavelength);
+            let scene_side = if i == 0 {
+                1.0
+            } else {
+                self.elements[i - 1].ior.at(ray.wavelength)
+            };
+            let eta = if from_film {
+                film_side / scene_side
+            } else {
+                scene_side / film_side
+            };
+
+            let cos_theta = -direction.dot(&normal);
+            let sin2_theta_t = eta * eta * (1.0 - cos_theta * cos_theta);
+
+            if sin2_theta_t > 1.0 {
+                return None;
+            }
+
+            direction = eta * direction + (eta * cos_theta - (1.0 - sin2_theta_t).sqrt()) * normal;
+        }
+
+        Some(Ray {
+            origin,
+            direction,
+            ..ray
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lens::LensSystem,
+        math::{Point3, Ray},
+    };
+
+    #[test]
+    fn focused_lens_images_a_point_onto_the_film() {
+        let mut lens = LensSystem::double_gauss();
+        let distance = 1000.0;
+        lens.focus(distance);
+
+        let rear = lens.rear_element();
+        let rear_z = rear.thickness;
+
+        // Rays from the centre of the film through different parts of the rear element all meet again on the axis
+        for x in [-0.3, -0.15, 0.1, 0.2, 0.3] {
+            let target = Point3::new(x * rear.aperture_radius, 0.0, rear_z);
+            let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), target);
+            let ray = lens.trace(ray, true).unwrap();
+            let t = (distance - ray.origin.z()) / ray.direction.z();
+
+            assert!(ray.at(t).x().abs() < 0.5, "{:?}", ray.at(t));
+        }
+
+        // Light from the side lands on the opposite side of the film
+        let origin = Point3::new(0.0, 100.0, distance);
+        let ray = Ray::new(origin, Point3::new(0.0, 0.0, lens.length()) - origin);
+        let ray = lens.trace(ray, false).unwrap();
+        assert!(ray.at(-ray.origin.z() / ray.direction.z()).y() < 0.0);
+    }
+
+    #[test]
+    fn f_number_sets_the_entrance_pupil() {
+        let mut lens = LensSystem::double_gauss();
+        let focal_length = lens.focal_length();
+
+        // The patent's lens is 50mm f/2 wide open
+        assert!((2.0 * lens.entrance_pupil_radius() * 2.0 - focal_length).abs() < 1.0);
+        assert!((focal_length - 50.0).abs() < 2.0, "{focal_length}");
+
+        lens.set_f_number(4.0);
+        let diameter = 2.0 * lens.entrance_pupil_radius();
+        assert!((focal_length / diameter - 4.0).abs() < 0.01, "{diameter}");
+    }
+
+    #[test]
+    fn lens_tables_reject_malformed_lines() {
+        assert!(LensSystem::read_table(&mut "10 2 1.5".as_bytes()).is_err());
+        assert!(LensSystem::read_table(&mut "10 2 1.5 lens".as_bytes()).is_err());
+        assert!(LensSystem::read_table(&mut "# nothing".as_bytes()).is_err());
+
+        let lens =
+            LensSystem::read_table(&mut "# a singlet\n50 5 1.5 20 60\n-50 45 0 20".as_bytes())
+                .unwrap();
+        assert_eq!(lens.elements.len(), 2);
+        assert!(lens.is_dispersive());
+        assert_eq!(lens.length(), 50.0);
+    }
+}
@@ -1,40 +1,161 @@
 use std::{
     io::{self, stderr, stdout, Write},
-    sync::mpsc,
+    sync::{mpsc, Arc},
     thread,
     time::Instant,
 };
 
-use hittables::HittableList;
+use hittables::{Hittable, HittableList};
 use math::{Color, Ray};
 use rand::{rngs::ThreadRng, thread_rng, Rng};
 
-use crate::{camera::Camera, image::Image};
+use crate::{
+    camera::Camera,
+    image::Image,
+    materials::HenyeyGreenstein,
+    spectrum::{rgb_to_emission, rgb_to_spectrum, wavelength_to_rgb, HERO_SAMPLES},
+};
 
+pub mod aabb;
 pub mod camera;
+pub mod fog;
 pub mod hittables;
 pub mod image;
+pub mod lens;
 pub mod materials;
 pub mod math;
+pub mod microfacet;
+pub mod scenes;
+pub mod spectrum;
+pub mod textures;
+pub mod thin_film;
+pub mod transform;
 pub mod utils;
+pub mod volumes;
+
+/// How light is carried along each path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Integrator {
+    /// Paths carry RGB, except after dispersion splits them into a single wavelength.
+    #[default]
+    Rgb,
+    /// Every path carries a single wavelength. Colours in the scene are turned into smooth spectra, and the samples
+    /// are accumulated in CIE XYZ before being converted to RGB.
+    Spectral,
+}
 
 fn ray_color(ray: Ray, world: &HittableList, rng: &mut ThreadRng, depth: usize) -> Color {
-    if depth <= 0 {
+    if depth == 0 {
         return Color::new(0.0, 0.0, 0.0);
     }
 
-    if let Some(rec) = world.hit(ray, 0.001, f64::INFINITY) {
+    let hit = world.hit(ray, 0.001, f64::INFINITY);
+
+    if let Some(fog) = &world.fog {
+        let t_max = hit.map_or(f64::INFINITY, |rec| rec.t);
+
+        if let Some(t) = fog.sample_collision(ray, t_max, rng) {
+            let scattered = Ray {
+                origin: ray.at(t),
+                direction: HenyeyGreenstein::sample_direction(fog.g, ray.direction, rng),
+                ..ray
+            };
+
+            return fog.color * ray_color(scattered, world, rng, depth - 1);
+        }
+    }
+
+    if let Some(rec) = hit {
+        let emitted = rec.material.emitted(ray, rec);
+
         return if let Some((attenuation, scattered)) = rec.material.scatter(ray, rec, rng) {
-            attenuation * ray_color(scattered, world, rng, depth - 1)
+            // Dispersion split the path, so from here on it only carries light at its hero wavelength
+            let attenuation = if scattered.dispersed && !ray.dispersed {
+                attenuation * wavelength_to_rgb(ray.wavelength)
+            } else {
+                attenuation
+            };
+
+            emitted + attenuation * ray_color(scattered, world, rng, depth - 1)
         } else {
-            Color::new(0.0, 0.0, 0.0)
+            emitted
         };
     }
 
-    let unit_direction = ray.direction.unit_vector();
-    let t = 0.5 * (unit_direction.y() + 1.0);
+    world.background.color(ray)
+}
+
+/// Like `ray_color`, but for a ray carrying light at the given `wavelengths`, the first being its hero wavelength.
+fn spectral_radiance(
+    ray: Ray,
+    wavelengths: &[f64; HERO_SAMPLES],
+    world: &HittableList,
+    rng: &mut ThreadRng,
+    depth: usize,
+) -> [f64; HERO_SAMPLES] {
+    let mut radiance = [0.0; HERO_SAMPLES];
+
+    if depth == 0 {
+        return radiance;
+    }
+
+    let hit = world.hit(ray, 0.001, f64::INFINITY);
+
+    if let Some(fog) = &world.fog {
+        let t_max = hit.map_or(f64::INFINITY, |rec| rec.t);
+
+        if let Some(t) = fog.sample_collision(ray, t_max, rng) {
+            let scattered = Ray {
+                origin: ray.at(t),
+                direction: HenyeyGreenstein::sample_direction(fog.g, ray.direction, rng),
+                ..ray
+            };
+            let incoming = spectral_radiance(scattered, wavelengths, world, rng, depth - 1);
+
+            for (i, value) in radiance.iter_mut().enumerate() {
+                *value = rgb_to_spectrum(fog.color, wavelengths[i]) * incoming[i];
+            }
+
+            return radiance;
+        }
+    }
+
+    let Some(rec) = hit else {
+        let background = world.background.color(ray);
 
-    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + Color::new(0.5, 0.7, 1.0) * t
+        for (i, value) in radiance.iter_mut().enumerate() {
+            *value = rgb_to_emission(background, wavelengths[i]);
+        }
+
+        return radiance;
+    };
+
+    for (i, value) in radiance.iter_mut().enumerate() {
+        let ray = Ray {
+            wavelength: wavelengths[i],
+            ..ray
+        };
+
+        *value = rec.material.emitted_spectral(ray, rec);
+    }
+
+    if let Some((attenuation, scattered)) = rec.material.scatter(ray, rec, rng) {
+        let mut incoming = spectral_radiance(scattered, wavelengths, world, rng, depth - 1);
+
+        // Dispersion split the path, so the companion wavelengths are dropped and the hero stands in for them
+        if scattered.dispersed && !ray.dispersed {
+            let hero = incoming[0];
+
+            incoming = [0.0; HERO_SAMPLES];
+            incoming[0] = HERO_SAMPLES as f64 * hero;
+        }
+
+        for (i, value) in radiance.iter_mut().enumerate() {
+            *value += rgb_to_spectrum(attenuation, wavelengths[i]) * incoming[i];
+        }
+    }
+
+    radiance
 }
 
 fn render(
@@ -42,10 +163,11 @@ fn render(
     samples_per_pixel: u32,
     max_depth: usize,
     world: &HittableList,
-    camera: Camera,
+    camera: &dyn Camera,
+    integrator: Integrator,
 ) -> Image {
     // Image
-    let aspect_ratio = camera.aspect_ratio;
+    let aspect_ratio = camera.aspect_ratio();
     let image_height = (image_width as f64 / aspect_ratio) as u32;
 
     // Render
@@ -60,11 +182,52 @@ fn render(
         for i in 0..image_width {
             let mut pixel_color = Color::new(0.0, 0.0, 0.0);
 
-            for _ in 0..samples_per_pixel {
+            for sample in 0..samples_per_pixel {
                 let u = (i as f64 + rng.gen::<f64>()) / (image_width - 1) as f64;
                 let v = (j as f64 + rng.gen::<f64>()) / (image_height - 1) as f64;
 
-                pixel_color += ray_color(camera.ray(u, v, &mut rng), &world, &mut rng, max_depth);
+                // Stratify the hero wavelengths so each pixel covers the spectrum evenly
+                let wavelengths = spectrum::sample_hero_wavelengths(
+                    (sample as f64 + rng.gen::<f64>()) / samples_per_pixel as f64,
+                );
+                let Some(ray) = camera.ray_at_wavelength(u, v, wavelengths[0], &mut rng) else {
+                    continue;
+                };
+
+                // A lens with chromatic aberration splits the path before it even reaches the scene
+                pixel_color += match integrator {
+                    Integrator::Rgb if ray.dispersed => {
+                        wavelength_to_rgb(ray.wavelength)
+                            * ray_color(ray, world, &mut rng, max_depth)
+                    }
+                    Integrator::Rgb => ray_color(ray, world, &mut rng, max_depth),
+                    Integrator::Spectral => {
+                        let mut radiance =
+                            spectral_radiance(ray, &wavelengths, world, &mut rng, max_depth);
+
+                        if ray.dispersed {
+                            let hero = radiance[0];
+
+                            radiance = [0.0; HERO_SAMPLES];
+                            radiance[0] = HERO_SAMPLES as f64 * hero;
+                        }
+
+                        // Accumulated as CIE XYZ, and only converted to RGB once the pixel is done
+                        wavelengths
+                            .iter()
+                            .zip(radiance)
+                            .map(|(&wavelength, value)| {
+                                spectrum::cie_xyz(wavelength)
+                                    * (value / spectrum::wavelength_pdf(wavelength))
+                            })
+                            .fold(Color::new(0.0, 0.0, 0.0), |a, b| a + b)
+                            / HERO_SAMPLES as f64
+                    }
+                };
+            }
+
+            if integrator == Integrator::Spectral {
+                pixel_color = spectrum::xyz_to_balanced_rgb(pixel_color);
             }
 
             image
@@ -76,83 +239,27 @@ fn render(
     image
 }
 
-/// Renders a scene given an image width, number of samples, max recursion depth, a world, and a camera
+/// Renders a scene on several threads and averages their results into one image.
 ///
-/// # Examples
-/// ```
-/// use rust_tracer::{
-///     camera::Camera,
-///     hittables::{self, Sphere},
-///     materials::{Dielectric, Lambertian, Metal},
-///     math::{Color, Point3, Vector3},
-/// };
-///
-/// // World
-/// let mut world = hittables::HittableList::new();
-///
-/// let ground_material = Lambertian::new(Color::new(0.8, 0.8, 0.0));
-/// let center_material = Lambertian::new(Color::new(0.1, 0.2, 0.5));
-/// let left_material = Dielectric::new(1.5);
-/// let right_material = Metal::new(Color::new(0.8, 0.6, 0.2), 0.0);
-///
-/// world.add(Sphere::new(
-///     Point3::new(0.0, -100.5, -1.0),
-///     100.0,
-///     ground_material,
-/// ));
-/// world.add(Sphere::new(
-///     Point3::new(0.0, 0.0, -1.0),
-///     0.5,
-///     center_material,
-/// ));
-/// world.add(Sphere::new(
-///     Point3::new(-1.0, 0.0, -1.0),
-///     0.5,
-///     left_material.clone(),
-/// ));
-/// world.add(Sphere::new(
-///     Point3::new(-1.0, 0.0, -1.0),
-///     -0.45,
-///     left_material,
-/// ));
-/// world.add(Sphere::new(
-///     Point3::new(1.0, 0.0, -1.0),
-///     0.5,
-///     right_material,
-/// ));
-///
-/// // Camera
-/// let camera = Camera::new(
-///     Point3::new(-2.0, 2.0, 1.0),
-///     Point3::new(0.0, 0.0, -1.0),
-///     Vector3(0.0, 1.0, 0.0),
-///     20.0,
-///     16.0 / 9.0,
-///     0.1,
-///     10.0
-/// );
-///
-/// // Render
-/// rust_tracer::render_to_stdout(400, 100, 50, &world, &camera);
-/// ```
-pub fn render_to_stdout<F>(
+/// Each thread calls `build_world` to get its own copy of the world, and takes an equal share of the samples.
+pub fn render_image<F>(
     image_width: u32,
     samples_per_pixel: u32,
     max_depth: usize,
     build_world: F,
-    camera: Camera,
+    camera: Arc<dyn Camera>,
     threads: usize,
-) -> io::Result<()>
+    integrator: Integrator,
+) -> Image
 where
     F: Fn() -> HittableList + Send + 'static + Copy,
 {
-    let now = Instant::now();
-
     let (tx, rx) = mpsc::channel();
-    let image_height = (image_width as f64 / camera.aspect_ratio) as u32;
+    let image_height = (image_width as f64 / camera.aspect_ratio()) as u32;
 
     for _ in 0..threads {
         let tx = tx.clone();
+        let camera = camera.clone();
 
         thread::spawn(move || {
             tx.send(render(
@@ -160,7 +267,8 @@ where
                 samples_per_pixel / threads as u32,
                 max_depth,
                 &build_world(),
-                camera,
+                camera.as_ref(),
+                integrator,
             ))
             .unwrap();
         });
@@ -172,7 +280,100 @@ where
         results.push(rx.recv().unwrap());
     }
 
-    Image::average(results, image_width, image_height).write_as_ppm(&mut stdout().lock())?;
+    Image::average(results, image_width, image_height) * camera.exposure()
+}
+
+/// Renders a scene given an image width, number of samples, max recursion depth, a world builder, a camera, a
+/// thread count and an integrator, and writes it to stdout as a PPM
+///
+/// # Examples
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use rust_tracer::{
+///     Integrator,
+///     camera::{FieldOfView, PerspectiveCamera},
+///     hittables::{self, Sphere},
+///     materials::{Dielectric, Lambertian, Metal},
+///     math::{Color, Point3},
+/// };
+///
+/// // World
+/// fn build_world() -> hittables::HittableList {
+///     let mut world = hittables::HittableList::new();
+///
+///     let ground_material = Lambertian::new(Color::new(0.8, 0.8, 0.0));
+///     let center_material = Lambertian::new(Color::new(0.1, 0.2, 0.5));
+///     let right_material = Metal::new(Color::new(0.8, 0.6, 0.2), 0.0);
+///
+///     world.add(Sphere::new(
+///         Point3::new(0.0, -100.5, -1.0),
+///         100.0,
+///         ground_material,
+///     ));
+///     world.add(Sphere::new(
+///         Point3::new(0.0, 0.0, -1.0),
+///         0.5,
+///         center_material,
+///     ));
+///     world.add(Sphere::new(
+///         Point3::new(-1.0, 0.0, -1.0),
+///         0.5,
+///         Dielectric::new(1.5),
+///     ));
+///     world.add(Sphere::new(
+///         Point3::new(-1.0, 0.0, -1.0),
+///         -0.45,
+///         Dielectric::new(1.5),
+///     ));
+///     world.add(Sphere::new(
+///         Point3::new(1.0, 0.0, -1.0),
+///         0.5,
+///         right_material,
+///     ));
+///
+///     world
+/// }
+///
+/// // Camera
+/// let camera = Arc::new(
+///     PerspectiveCamera::builder()
+///         .look_from(Point3::new(-2.0, 2.0, 1.0))
+///         .look_at(Point3::new(0.0, 0.0, -1.0))
+///         .field_of_view(FieldOfView::Vertical(20.0))
+///         .aspect_ratio(16.0 / 9.0)
+///         .aperture(0.1)
+///         .focus_distance(10.0)
+///         .build(),
+/// );
+///
+/// // Render
+/// rust_tracer::render_to_stdout(400, 100, 50, build_world, camera, 4, Integrator::Rgb).unwrap();
+/// ```
+pub fn render_to_stdout<F>(
+    image_width: u32,
+    samples_per_pixel: u32,
+    max_depth: usize,
+    build_world: F,
+    camera: Arc<dyn Camera>,
+    threads: usize,
+    integrator: Integrator,
+) -> io::Result<()>
+where
+    F: Fn() -> HittableList + Send + 'static + Copy,
+{
+    let now = Instant::now();
+
+    render_image(
+        image_width,
+        samples_per_pixel,
+        max_depth,
+        build_world,
+        camera,
+        threads,
+        integrator,
+    )
+    .write_as_ppm(&mut stdout().lock())?;
 
     eprintln!("\nCompleted in {} seconds.", now.elapsed().as_secs());
 
@@ -1,27 +1,82 @@
-use rand::{rngs::ThreadRng, Rng};
+use std::{f64::consts::PI, sync::Arc};
+
+use rand::{rngs::ThreadRng, thread_rng, Rng};
 
 use crate::{
     hittables::HitRecord,
-    math::{Color, Ray, Vector3},
-    utils::{random_in_unit_sphere, random_unit_vector, reflect, refract},
+    math::{Color, Onb, Point3, Ray, Vector3},
+    microfacet::Ggx,
+    spectrum::{rgb_to_emission, rgb_to_spectrum, Ior, Spectrum},
+    textures::{SolidColor, Texture},
+    thin_film::{reflectance_for_ray, rgb_at_wavelength, ThinFilm},
+    utils::{
+        fresnel_conductor, fresnel_dielectric, fresnel_schlick, lerp, luminance,
+        random_cosine_direction, random_in_unit_sphere, random_unit_vector, reflect, refract,
+    },
 };
 
 pub trait Material: Send + Sync {
     fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)>;
+
+    /// Light given off by the surface itself, independent of any light arriving at it.
+    fn emitted(&self, _ray: Ray, _rec: HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// The light given off at the ray's wavelength, for the spectral integrator.
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        rgb_to_emission(self.emitted(ray, rec), ray.wavelength)
+    }
+
+    /// The chance that the surface has a hole at the hit, so that objects ignore the intersection and the ray
+    /// carries on through.
+    fn cut_out_probability(&self, _rec: HitRecord) -> f64 {
+        0.0
+    }
+
+    /// Decides whether this particular hit falls through a hole, with [`Material::cut_out_probability`].
+    fn is_cut_out(&self, rec: HitRecord) -> bool {
+        let probability = self.cut_out_probability(rec);
+
+        probability > 0.0 && thread_rng().gen::<f64>() < probability
+    }
+}
+
+/// Lets several objects share one material, e.g. the sides of a box.
+impl Material for Arc<dyn Material> {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        self.as_ref().scatter(ray, rec, rng)
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        self.as_ref().emitted(ray, rec)
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        self.as_ref().emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        self.as_ref().cut_out_probability(rec)
+    }
 }
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Box<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo))
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>) -> Box<Self> {
         Box::new(Self { albedo })
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
         let scatter_direction = rec.normal + random_unit_vector(rng);
 
         // Catch degenerate scatter direction
@@ -31,7 +86,268 @@ impl Material for Lambertian {
             scatter_direction
         };
 
-        Some((self.albedo, Ray::new(rec.point, scatter_direction)))
+        Some((
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: scatter_direction,
+                ..ray
+            },
+        ))
+    }
+}
+
+/// Rough diffuse surfaces such as clay, plaster or the moon, which look flatter than [`Lambertian`] because their
+/// tiny facets reflect light back towards where it came from (Oren–Nayar).
+pub struct OrenNayar {
+    pub albedo: Box<dyn Texture>,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    /// `sigma` is the standard deviation of the facet angles in degrees; 0 is the same as [`Lambertian`].
+    pub fn new(albedo: Color, sigma: f64) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo), sigma)
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>, sigma: f64) -> Box<Self> {
+        let sigma2 = sigma.to_radians().powi(2);
+
+        Box::new(Self {
+            albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        })
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+        let wi = random_cosine_direction(rng);
+
+        let sin_theta = |w: Vector3| (1.0 - w.z() * w.z()).max(0.0).sqrt();
+        let (sin_o, sin_i) = (sin_theta(wo), sin_theta(wi));
+
+        // The cosine of the azimuthal angle between the two directions
+        let cos_phi = if sin_o > 1e-6 && sin_i > 1e-6 {
+            ((wo.x() * wi.x() + wo.y() * wi.y()) / (sin_o * sin_i)).max(0.0)
+        } else {
+            0.0
+        };
+
+        let cos_o = wo.z().abs().max(1e-6);
+        let (sin_alpha, tan_beta) = if wi.z() > cos_o {
+            (sin_o, sin_i / wi.z())
+        } else {
+            (sin_i, sin_o / cos_o)
+        };
+
+        // Cosine-weighted sampling cancels the cosine and the 1/π, leaving the Oren–Nayar factor
+        let factor = self.a + self.b * cos_phi * sin_alpha * tan_beta;
+
+        Some((
+            factor * self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// A diffuse surface that sends extra light straight back towards its source, like road signs or dusty ground.
+///
+/// A `retro` fraction of the light goes into a lobe around the direction it came from, with higher `sharpness`
+/// making the lobe narrower; the rest scatters like [`Lambertian`].
+pub struct RetroReflective {
+    pub albedo: Box<dyn Texture>,
+    pub retro: f64,
+    pub sharpness: f64,
+}
+
+impl RetroReflective {
+    pub fn new(albedo: Color, retro: f64, sharpness: f64) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo), retro, sharpness)
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>, retro: f64, sharpness: f64) -> Box<Self> {
+        Box::new(Self {
+            albedo,
+            retro: retro.clamp(0.0, 1.0),
+            sharpness: sharpness.max(0.0),
+        })
+    }
+
+    /// The density of the retro-reflective lobe, a normalized `cos^n` lobe around `wo`.
+    fn lobe(&self, wo: Vector3, wi: Vector3) -> f64 {
+        (self.sharpness + 1.0) / (2.0 * PI) * wo.dot(&wi).max(0.0).powf(self.sharpness)
+    }
+}
+
+impl Material for RetroReflective {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+
+        // Pick one of the two lobes, but weight by the density of both so either choice gives the same answer
+        let wi = if rng.gen::<f64>() < self.retro {
+            let cos_theta = rng.gen::<f64>().powf(1.0 / (self.sharpness + 1.0));
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * rng.gen::<f64>();
+
+            Onb::new(wo).local(Vector3::new(
+                phi.cos() * sin_theta,
+                phi.sin() * sin_theta,
+                cos_theta,
+            ))
+        } else {
+            random_cosine_direction(rng)
+        };
+
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        let lobe = self.lobe(wo, wi);
+        let brdf = (1.0 - self.retro) / PI + self.retro * lobe;
+        let pdf = (1.0 - self.retro) * wi.z() / PI + self.retro * lobe;
+
+        Some((
+            brdf * wi.z() / pdf * self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// Velvet and other fabrics with fibres standing up from the surface, which look dark face-on and glow towards
+/// their silhouettes (the "Charlie" sheen of Estevez and Kulla).
+pub struct Velvet {
+    pub albedo: Box<dyn Texture>,
+    /// How spread out the fibres are, from 0 (combed flat) to 1.
+    pub roughness: f64,
+}
+
+impl Velvet {
+    pub fn new(albedo: Color, roughness: f64) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo), roughness)
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>, roughness: f64) -> Box<Self> {
+        Box::new(Self {
+            albedo,
+            roughness: roughness.clamp(0.05, 1.0),
+        })
+    }
+
+    /// The density of fibre-facing microfacets, `(2 + 1/α) sin^(1/α)θ / 2π`.
+    fn d(&self, wm: Vector3) -> f64 {
+        let inverse = 1.0 / self.roughness;
+        let sin_theta = (1.0 - wm.z() * wm.z()).max(0.0).sqrt();
+
+        (2.0 + inverse) * sin_theta.powf(inverse) / (2.0 * PI)
+    }
+}
+
+impl Material for Velvet {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        // Either sample a microfacet normal from D(m) cos(m), whose CDF in sin(θ) is sin^(1/α + 2), and reflect
+        // about it, or fall back to a cosine-weighted direction, which covers the grazing half vectors better
+        let wi = if rng.gen::<bool>() {
+            let sin_theta = rng.gen::<f64>().powf(1.0 / (1.0 / self.roughness + 2.0));
+            let cos_theta = (1.0 - sin_theta * sin_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * rng.gen::<f64>();
+            let wm = Vector3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+            reflect(-wo, wm)
+        } else {
+            random_cosine_direction(rng)
+        };
+
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        let wm = (wo + wi).unit_vector();
+        let d = self.d(wm);
+
+        // Neubelt and Pettineo's visibility term, which keeps the grazing peak from blowing up
+        let brdf = d / (4.0 * (wi.z() + wo.z() - wi.z() * wo.z()));
+        let pdf = 0.5 * wi.z() / PI + 0.5 * d * wm.z() / (4.0 * wo.dot(&wm).max(1e-6));
+
+        Some((
+            brdf * wi.z() / pdf * self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Box<dyn Texture>,
+    /// The exact spectrum of the light, used instead of `emit` by the spectral integrator.
+    pub spectrum: Option<Spectrum>,
+}
+
+impl DiffuseLight {
+    pub fn new(color: Color) -> Box<Self> {
+        Self::textured(SolidColor::new(color))
+    }
+
+    pub fn textured(emit: Box<dyn Texture>) -> Box<Self> {
+        Box::new(Self {
+            emit,
+            spectrum: None,
+        })
+    }
+
+    /// A light with a physical spectrum, such as [`Spectrum::blackbody`], at a luminance of `intensity`.
+    pub fn spectral(spectrum: Spectrum, intensity: f64) -> Box<Self> {
+        let spectrum = spectrum * intensity;
+
+        Box::new(Self {
+            emit: SolidColor::new(spectrum.to_rgb()),
+            spectrum: Some(spectrum),
+        })
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: Ray, _rec: HitRecord, _rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _ray: Ray, rec: HitRecord) -> Color {
+        // Lights only shine out of their front face
+        if rec.front_face {
+            self.emit.value(rec.u, rec.v, rec.point)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        match &self.spectrum {
+            Some(spectrum) if rec.front_face => spectrum.at(ray.wavelength),
+            Some(_) => 0.0,
+            None => rgb_to_emission(self.emitted(ray, rec), ray.wavelength),
+        }
     }
 }
 
@@ -52,10 +368,11 @@ impl Metal {
 impl Material for Metal {
     fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
         let reflected = reflect(ray.direction.unit_vector(), rec.normal);
-        let scattered = Ray::new(
-            rec.point,
-            reflected + self.fuzz * random_in_unit_sphere(rng),
-        );
+        let scattered = Ray {
+            origin: rec.point,
+            direction: reflected + self.fuzz * random_in_unit_sphere(rng),
+            ..ray
+        };
         if Vector3::dot(&scattered.direction, &rec.normal) > 0.0 {
             Some((self.albedo, scattered))
         } else {
@@ -64,14 +381,191 @@ impl Material for Metal {
     }
 }
 
+/// A physically based metal: GGX microfacets with the exact Fresnel reflectance of its complex index of refraction.
+///
+/// Unlike [`Metal`], roughness doesn't gain or lose energy arbitrarily, and the colour shifts towards white at
+/// grazing angles like real metals do.
+pub struct Conductor {
+    /// The real part of the index of refraction, per RGB channel.
+    pub eta: Color,
+    /// The extinction coefficient (imaginary part of the index of refraction), per RGB channel.
+    pub k: Color,
+    pub distribution: Ggx,
+    /// An optional transparent coating, such as the oxide layer that makes heated metal iridescent.
+    pub film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64) -> Box<Self> {
+        Self::anisotropic(eta, k, roughness, 0.0)
+    }
+
+    /// A conductor with brushed-looking highlights, stretched more the closer `anisotropy` is to 1.
+    pub fn anisotropic(eta: Color, k: Color, roughness: f64, anisotropy: f64) -> Box<Self> {
+        Box::new(Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness, anisotropy),
+            film: None,
+        })
+    }
+
+    pub fn with_thin_film(mut self: Box<Self>, film: ThinFilm) -> Box<Self> {
+        self.film = Some(film);
+        self
+    }
+
+    fn fresnel(&self, cos_theta: f64, ray: Ray) -> Color {
+        match self.film {
+            Some(film) => reflectance_for_ray(ray, |lambda| {
+                film.reflectance_conductor(
+                    cos_theta,
+                    rgb_at_wavelength(self.eta, lambda),
+                    rgb_at_wavelength(self.k, lambda),
+                    lambda,
+                )
+            }),
+            None => fresnel_conductor(cos_theta, self.eta, self.k),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Box<Self> {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Box<Self> {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Box<Self> {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Box<Self> {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+
+        if self.distribution.is_smooth() {
+            let direction = reflect(ray.direction.unit_vector(), rec.normal);
+
+            return Some((
+                self.fresnel(wo.z().max(0.0), ray),
+                Ray {
+                    origin: rec.point,
+                    direction,
+                    ..ray
+                },
+            ));
+        }
+
+        let wm = self.distribution.sample_visible_normal(wo, rng);
+        let wi = reflect(-wo, wm);
+
+        // Reflected into the surface, i.e. shadowed by other microfacets
+        if wi.z() <= 0.0 {
+            return None;
+        }
+
+        // Sampling visible normals leaves only Fresnel and the shadowing of the outgoing direction
+        let attenuation = self.fresnel(wo.dot(&wm), ray)
+            * (self.distribution.g(wo, wi) / self.distribution.g1(wo));
+
+        Some((
+            attenuation,
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// Converts "light keeps `color` of itself after travelling `distance`" into an absorption coefficient.
+pub fn absorption_from_transmission(color: Color, distance: f64) -> Color {
+    Color::new(
+        -color.x().max(1e-6).ln() / distance,
+        -color.y().max(1e-6).ln() / distance,
+        -color.z().max(1e-6).ln() / distance,
+    )
+}
+
+/// How much light is left after travelling through an absorbing medium to reach `rec` (Beer–Lambert law).
+///
+/// A ray hitting the back face of a closed surface must have travelled inside it, so only those rays are attenuated.
+/// Objects nested inside an absorbing one interrupt this, so the stretch up to them isn't absorbed.
+fn transmittance(absorption: Color, ray: Ray, rec: &HitRecord) -> Color {
+    if rec.front_face || absorption.near_zero() {
+        return Color::new(1.0, 1.0, 1.0);
+    }
+
+    let distance = rec.t * ray.direction.length();
+
+    Color::new(
+        (-absorption.x() * distance).exp(),
+        (-absorption.y() * distance).exp(),
+        (-absorption.z() * distance).exp(),
+    )
+}
+
 #[derive(Clone, Copy)]
 pub struct Dielectric {
-    pub refraction_index: f64,
+    pub refraction_index: Ior,
+    /// How much of each RGB channel is absorbed per unit of distance travelled inside.
+    pub absorption: Color,
+    /// An optional coating on the outside, such as the anti-reflective coating on a lens. A soap bubble is a film
+    /// on a dielectric with an index of refraction of 1.
+    pub film: Option<ThinFilm>,
 }
 
 impl Dielectric {
-    pub fn new(refraction_index: f64) -> Box<Self> {
-        Box::new(Self { refraction_index })
+    /// Clear glass; pass an [`Ior`] preset such as [`Ior::bk7`] instead of a number to get dispersion.
+    pub fn new(refraction_index: impl Into<Ior>) -> Box<Self> {
+        Self::absorbing(refraction_index, Color::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn absorbing(refraction_index: impl Into<Ior>, absorption: Color) -> Box<Self> {
+        Box::new(Self {
+            refraction_index: refraction_index.into(),
+            absorption,
+            film: None,
+        })
+    }
+
+    pub fn with_thin_film(mut self: Box<Self>, film: ThinFilm) -> Box<Self> {
+        self.film = Some(film);
+        self
+    }
+
+    /// Coloured glass that lets through `color` of the light after it has travelled `distance` inside, so thicker
+    /// parts come out darker and more saturated.
+    pub fn tinted(refraction_index: impl Into<Ior>, color: Color, distance: f64) -> Box<Self> {
+        Self::absorbing(
+            refraction_index,
+            absorption_from_transmission(color, distance),
+        )
     }
 
     fn reflectance(cosine: f64, refractive_index: f64) -> f64 {
@@ -83,10 +577,11 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let refraction_index = self.refraction_index.at(ray.wavelength);
         let refraction_ratio = if rec.front_face {
-            1.0 / self.refraction_index
+            1.0 / refraction_index
         } else {
-            self.refraction_index
+            refraction_index
         };
 
         let unit_direction = ray.direction.unit_vector();
@@ -94,14 +589,1423 @@ impl Material for Dielectric {
         let cos_theta = (-unit_direction).dot(&rec.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let direction = if refraction_ratio * sin_theta > 1.0
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen()
-        {
+        let cannot_refract = refraction_ratio * sin_theta > 1.0;
+
+        let (reflects, weight) = if let Some(film) = self.film {
+            // The film's reflectance differs per colour, so choose by its average and reweight each channel
+            let reflectance = reflectance_for_ray(ray, |lambda| {
+                let n = self.refraction_index.at(lambda);
+                let (outside, substrate) = if rec.front_face { (1.0, n) } else { (n, 1.0) };
+
+                film.reflectance_dielectric(cos_theta, outside, substrate, lambda)
+            });
+            let probability =
+                ((reflectance.x() + reflectance.y() + reflectance.z()) / 3.0).clamp(1e-3, 1.0);
+
+            if cannot_refract {
+                // Not a random choice, so there's no probability to divide by
+                (true, reflectance)
+            } else if rng.gen::<f64>() < probability {
+                (true, reflectance / probability)
+            } else {
+                let transmitted = Color::new(1.0, 1.0, 1.0) - reflectance;
+
+                (false, transmitted / (1.0 - probability))
+            }
+        } else {
+            let reflects =
+                cannot_refract || Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen();
+
+            (reflects, Color::new(1.0, 1.0, 1.0))
+        };
+
+        let direction = if reflects {
             reflect(unit_direction, rec.normal)
         } else {
             refract(unit_direction, rec.normal, refraction_ratio)
         };
 
-        Some((Color::new(1.0, 1.0, 1.0), Ray::new(rec.point, direction)))
+        // Refraction bends each wavelength differently, so the path splits and from here on only carries light at
+        // its hero wavelength. The integrator takes care of weighting it.
+        let splits = !reflects && self.refraction_index.is_dispersive();
+
+        Some((
+            weight * transmittance(self.absorption, ray, &rec),
+            Ray {
+                origin: rec.point,
+                direction,
+                dispersed: ray.dispersed || splits,
+                ..ray
+            },
+        ))
+    }
+}
+
+/// Frosted or etched glass: a dielectric whose surface is made of GGX microfacets that both reflect and refract.
+pub struct RoughDielectric {
+    pub refraction_index: f64,
+    pub distribution: Ggx,
+    /// How much of each RGB channel is absorbed per unit of distance travelled inside.
+    pub absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(refraction_index: f64, roughness: f64) -> Box<Self> {
+        Self::absorbing(refraction_index, roughness, Color::new(0.0, 0.0, 0.0))
+    }
+
+    pub fn absorbing(refraction_index: f64, roughness: f64, absorption: Color) -> Box<Self> {
+        Box::new(Self {
+            refraction_index,
+            distribution: Ggx::new(roughness, 0.0),
+            absorption,
+        })
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        // Ratio of the index of refraction on the far side of the surface to the one the ray is in
+        let eta = if rec.front_face {
+            self.refraction_index
+        } else {
+            1.0 / self.refraction_index
+        };
+
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+
+        let wm = if self.distribution.is_smooth() {
+            Vector3::new(0.0, 0.0, 1.0)
+        } else {
+            self.distribution.sample_visible_normal(wo, rng)
+        };
+
+        // Choosing between reflection and refraction in proportion to the Fresnel term cancels it out of the weight
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let wi = if rng.gen::<f64>() < reflectance {
+            let wi = reflect(-wo, wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = refract(-wo, wm, 1.0 / eta);
+            if wi.z() >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        let attenuation = if self.distribution.is_smooth() {
+            1.0
+        } else {
+            self.distribution.g(wo, wi) / self.distribution.g1(wo)
+        };
+
+        Some((
+            attenuation * transmittance(self.absorption, ray, &rec),
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// Translucent materials such as skin, wax, marble and milk, where light enters the object, scatters around inside
+/// and comes out somewhere else.
+///
+/// Light takes a random walk through the inside: whenever a ray travelling inside reaches the boundary, it may have
+/// been scattered by the medium on the way, in which case it carries on in a new direction from there. This relies
+/// on the object being closed and not overlapping anything else.
+///
+/// When each colour travels a different distance, light entering the object splits like it does with dispersion,
+/// and each path carries on at its hero wavelength alone.
+pub struct Subsurface {
+    /// The colour of the object once light has scattered around inside it many times.
+    pub albedo: Color,
+    /// The average distance light travels inside between scattering events, per RGB channel. Longer distances make
+    /// the object more translucent.
+    pub mean_free_path: Color,
+    pub refraction_index: f64,
+}
+
+impl Subsurface {
+    pub fn new(albedo: Color, mean_free_path: Color, refraction_index: f64) -> Box<Self> {
+        Box::new(Self {
+            albedo,
+            mean_free_path,
+            refraction_index,
+        })
+    }
+
+    /// A material with measured scattering and absorption coefficients, in inverse units of distance.
+    pub fn from_coefficients(
+        scattering: Color,
+        absorption: Color,
+        refraction_index: f64,
+    ) -> Box<Self> {
+        let extinction = scattering + absorption;
+        let albedo = |scattering: f64, extinction: f64| {
+            Self::multiple_scattering_albedo(scattering / extinction)
+        };
+
+        Self::new(
+            Color::new(
+                albedo(scattering.x(), extinction.x()),
+                albedo(scattering.y(), extinction.y()),
+                albedo(scattering.z(), extinction.z()),
+            ),
+            Color::new(1.0, 1.0, 1.0) / extinction,
+            refraction_index,
+        )
+    }
+
+    // Converting between the probability of surviving one scattering event and the overall albedo, using the fit
+    // from Chiang et al. 2016, "Practical and Controllable Subsurface Scattering for Production Path Tracing"
+
+    fn single_scattering_albedo(albedo: f64) -> f64 {
+        let albedo = albedo.clamp(0.0, 1.0);
+        let term = 4.09712 + 4.20863 * albedo
+            - (9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo).sqrt();
+
+        1.0 - term * term
+    }
+
+    fn multiple_scattering_albedo(single_scattering_albedo: f64) -> f64 {
+        let c = 4.09712 - (1.0 - single_scattering_albedo.clamp(0.0, 1.0)).sqrt();
+
+        // The fit's quadratic terms cancel out, leaving a linear equation
+        ((9.59217 - c * c) / (2.0 * 4.20863 * c - 41.6808)).clamp(0.0, 1.0)
+    }
+
+    /// Whether light of different colours travels different distances inside, which splits paths.
+    pub fn is_chromatic(&self) -> bool {
+        let Vector3(r, g, b) = self.mean_free_path;
+
+        r != g || g != b
+    }
+
+    // Measurements from Jensen et al. 2001, "A Practical Model for Subsurface Light Transport", where `scale` is
+    // the size of a millimetre in scene units.
+
+    pub fn skin(scale: f64) -> Box<Self> {
+        Self::from_coefficients(
+            Color::new(0.74, 0.88, 1.01) / scale,
+            Color::new(0.032, 0.17, 0.48) / scale,
+            1.3,
+        )
+    }
+
+    pub fn marble(scale: f64) -> Box<Self> {
+        Self::from_coefficients(
+            Color::new(2.19, 2.62, 3.00) / scale,
+            Color::new(0.0021, 0.0041, 0.0071) / scale,
+            1.3,
+        )
+    }
+
+    pub fn milk(scale: f64) -> Box<Self> {
+        Self::from_coefficients(
+            Color::new(2.55, 3.21, 3.77) / scale,
+            Color::new(0.0011, 0.0024, 0.014) / scale,
+            1.3,
+        )
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let splits = self.is_chromatic();
+
+        if !rec.front_face {
+            let (mean_free_path, albedo) = if ray.dispersed || splits {
+                let albedo =
+                    Self::single_scattering_albedo(rgb_to_spectrum(self.albedo, ray.wavelength));
+
+                (
+                    rgb_to_spectrum(self.mean_free_path, ray.wavelength),
+                    Color::new(albedo, albedo, albedo),
+                )
+            } else {
+                (
+                    self.mean_free_path.x(),
+                    Color::new(
+                        Self::single_scattering_albedo(self.albedo.x()),
+                        Self::single_scattering_albedo(self.albedo.y()),
+                        Self::single_scattering_albedo(self.albedo.z()),
+                    ),
+                )
+            };
+
+            let ray_length = ray.direction.length();
+            let distance = -(1.0 - rng.gen::<f64>()).ln() * mean_free_path.max(1e-9);
+
+            // Scattered by the medium before reaching the boundary
+            if distance < rec.t * ray_length {
+                return Some((
+                    albedo,
+                    Ray {
+                        origin: ray.at(distance / ray_length),
+                        direction: random_unit_vector(rng),
+                        dispersed: ray.dispersed || splits,
+                        ..ray
+                    },
+                ));
+            }
+        }
+
+        let (near, far) = if rec.front_face {
+            (1.0, self.refraction_index)
+        } else {
+            (self.refraction_index, 1.0)
+        };
+        let unit_direction = ray.direction.unit_vector();
+        let cos_theta = (-unit_direction).dot(&rec.normal).min(1.0);
+        let reflects = fresnel_dielectric(cos_theta, far / near) > rng.gen();
+
+        let direction = if reflects {
+            reflect(unit_direction, rec.normal)
+        } else {
+            refract(unit_direction, rec.normal, near / far)
+        };
+
+        Some((
+            Color::new(1.0, 1.0, 1.0),
+            Ray {
+                origin: rec.point,
+                direction,
+                // Light reflecting off the outside never enters, so it doesn't split
+                dispersed: ray.dispersed || (splits && !(reflects && rec.front_face)),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// The settings of a [`Principled`] material, for when they don't vary over the surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrincipledParams {
+    pub base_color: Color,
+    /// Blends from a dielectric (0) to a metal (1) whose reflections take on `base_color`.
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Strength of the dielectric specular highlight; 0.5 gives the physically correct amount for `ior`.
+    pub specular: f64,
+    /// Tints the dielectric specular highlight towards `base_color`.
+    pub specular_tint: f64,
+    /// A soft rim of light at grazing angles, as seen on cloth.
+    pub sheen: f64,
+    /// Strength of a clear, glossy varnish layer on top.
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    /// Blends from opaque (0) to glass-like refraction (1).
+    pub transmission: f64,
+    pub ior: f64,
+    pub emission: Color,
+}
+
+impl Default for PrincipledParams {
+    fn default() -> Self {
+        Self {
+            base_color: Color::new(0.8, 0.8, 0.8),
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.1,
+            transmission: 0.0,
+            ior: 1.5,
+            emission: Color::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// A Disney-style "principled" uber material that covers most real-world surfaces with a few intuitive settings.
+///
+/// Every setting can be driven by a texture; scalar settings read the texture's red channel. Each bounce picks
+/// one of the diffuse, specular, clearcoat and transmission lobes with probability proportional to roughly how
+/// much it contributes, and samples a direction from that lobe.
+pub struct Principled {
+    pub base_color: Box<dyn Texture>,
+    pub metallic: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+    pub specular: Box<dyn Texture>,
+    pub specular_tint: Box<dyn Texture>,
+    pub sheen: Box<dyn Texture>,
+    pub clearcoat: Box<dyn Texture>,
+    pub clearcoat_roughness: Box<dyn Texture>,
+    pub transmission: Box<dyn Texture>,
+    pub ior: Box<dyn Texture>,
+    pub emission: Box<dyn Texture>,
+}
+
+/// The settings of a [`Principled`] material looked up at one point.
+struct PrincipledSample {
+    base_color: Color,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    specular_tint: f64,
+    sheen: f64,
+    clearcoat: f64,
+    clearcoat_roughness: f64,
+    transmission: f64,
+    ior: f64,
+}
+
+impl Principled {
+    pub fn new(params: PrincipledParams) -> Box<Self> {
+        let scalar =
+            |value: f64| -> Box<dyn Texture> { SolidColor::new(Color::new(value, value, value)) };
+
+        Box::new(Self {
+            base_color: SolidColor::new(params.base_color),
+            metallic: scalar(params.metallic),
+            roughness: scalar(params.roughness),
+            specular: scalar(params.specular),
+            specular_tint: scalar(params.specular_tint),
+            sheen: scalar(params.sheen),
+            clearcoat: scalar(params.clearcoat),
+            clearcoat_roughness: scalar(params.clearcoat_roughness),
+            transmission: scalar(params.transmission),
+            ior: scalar(params.ior),
+            emission: SolidColor::new(params.emission),
+        })
+    }
+
+    fn sample_params(&self, rec: &HitRecord) -> PrincipledSample {
+        let (u, v, p) = (rec.u, rec.v, rec.point);
+        let scalar = |texture: &dyn Texture| texture.value(u, v, p).x().clamp(0.0, 1.0);
+
+        PrincipledSample {
+            base_color: self.base_color.value(u, v, p),
+            metallic: scalar(self.metallic.as_ref()),
+            roughness: scalar(self.roughness.as_ref()),
+            specular: self.specular.value(u, v, p).x().max(0.0),
+            specular_tint: scalar(self.specular_tint.as_ref()),
+            sheen: self.sheen.value(u, v, p).x().max(0.0),
+            clearcoat: scalar(self.clearcoat.as_ref()),
+            clearcoat_roughness: scalar(self.clearcoat_roughness.as_ref()),
+            transmission: scalar(self.transmission.as_ref()),
+            ior: self.ior.value(u, v, p).x().max(1.0),
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        let params = self.sample_params(&rec);
+        let white = Color::new(1.0, 1.0, 1.0);
+
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+        let specular_ggx = Ggx::new(params.roughness, 0.0);
+
+        let tint = if luminance(params.base_color) > 0.0 {
+            params.base_color / luminance(params.base_color)
+        } else {
+            white
+        };
+        let r0 = ((params.ior - 1.0) / (params.ior + 1.0)).powi(2);
+        let dielectric_f0 = 2.0 * params.specular * r0 * lerp(white, tint, params.specular_tint);
+        let f0 = lerp(dielectric_f0, params.base_color, params.metallic);
+
+        // The varnish reflects some light before it reaches the layers below
+        let clearcoat_reflectance =
+            0.25 * params.clearcoat * fresnel_schlick(white * 0.04, wo.z()).x();
+        let base_weight = 1.0 - clearcoat_reflectance;
+
+        // Light reflected by the specular layer never reaches the diffuse one, and the transmission lobe already
+        // reflects its own share, so the specular lobe only covers the part that isn't transmissive
+        let dielectric_reflectance = luminance(fresnel_schlick(dielectric_f0, wo.z()));
+        let diffuse_weight =
+            (1.0 - params.metallic) * (1.0 - params.transmission) * (1.0 - dielectric_reflectance);
+        let transmission_weight = (1.0 - params.metallic) * params.transmission;
+        let specular_weight = 1.0 - transmission_weight;
+
+        // Rays inside a transmissive object can only carry on refracting through it, while opaque surfaces look the
+        // same from both sides, since the frame's normal always faces the incoming ray
+        let inside = !rec.front_face && transmission_weight > 0.0;
+        let lobe_weights = if !inside {
+            [
+                diffuse_weight * (luminance(params.base_color) + params.sheen),
+                specular_weight * luminance(fresnel_schlick(f0, wo.z())),
+                clearcoat_reflectance,
+                transmission_weight,
+            ]
+        } else {
+            [0.0, 0.0, 0.0, transmission_weight]
+        };
+
+        let total: f64 = lobe_weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = rng.gen::<f64>() * total;
+        let mut lobe = 0;
+        while lobe < 3 && (pick >= lobe_weights[lobe] || lobe_weights[lobe] == 0.0) {
+            pick -= lobe_weights[lobe];
+            lobe += 1;
+        }
+        let probability = lobe_weights[lobe] / total;
+
+        let (attenuation, wi) = match lobe {
+            // Disney diffuse, with its grazing retro-reflection, plus sheen
+            0 => {
+                let wi = random_cosine_direction(rng);
+                let wh = (wi + wo).unit_vector();
+                let cos_d = wi.dot(&wh);
+
+                let fd90 = 0.5 + 2.0 * params.roughness * cos_d * cos_d;
+                let schlick_weight = |cos: f64| (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+                let retro = (1.0 + (fd90 - 1.0) * schlick_weight(wi.z()))
+                    * (1.0 + (fd90 - 1.0) * schlick_weight(wo.z()));
+
+                let sheen_color = lerp(white, tint, 0.5);
+                let sheen = PI * params.sheen * schlick_weight(cos_d) * sheen_color;
+
+                (
+                    diffuse_weight * base_weight * (params.base_color * retro + sheen),
+                    wi,
+                )
+            }
+            // Specular reflection, coloured by the base colour as the surface becomes metallic
+            1 => {
+                let wm = specular_ggx.sample_visible_normal(wo, rng);
+                let wi = reflect(-wo, wm);
+                if wi.z() <= 0.0 {
+                    return None;
+                }
+
+                (
+                    specular_weight
+                        * base_weight
+                        * fresnel_schlick(f0, wo.dot(&wm))
+                        * (specular_ggx.g(wo, wi) / specular_ggx.g1(wo)),
+                    wi,
+                )
+            }
+            // Clearcoat
+            2 => {
+                let ggx = Ggx::new(params.clearcoat_roughness, 0.0);
+                let wm = ggx.sample_visible_normal(wo, rng);
+                let wi = reflect(-wo, wm);
+                if wi.z() <= 0.0 {
+                    return None;
+                }
+
+                let fresnel = fresnel_schlick(white * 0.04, wo.dot(&wm)).x();
+                let weight = 0.25 * params.clearcoat * fresnel * ggx.g(wo, wi) / ggx.g1(wo);
+
+                (white * weight, wi)
+            }
+            // Transmission through a rough dielectric interface, tinted by the base colour on the way in
+            _ => {
+                let eta = if rec.front_face {
+                    params.ior
+                } else {
+                    1.0 / params.ior
+                };
+
+                let wm = specular_ggx.sample_visible_normal(wo, rng);
+                let wi = if rng.gen::<f64>() < fresnel_dielectric(wo.dot(&wm), eta) {
+                    let wi = reflect(-wo, wm);
+                    if wi.z() <= 0.0 {
+                        return None;
+                    }
+                    wi
+                } else {
+                    let wi = refract(-wo, wm, 1.0 / eta);
+                    if wi.z() >= 0.0 {
+                        return None;
+                    }
+                    wi
+                };
+
+                let shadowing = specular_ggx.g(wo, wi) / specular_ggx.g1(wo);
+                let weight = if !inside {
+                    transmission_weight * base_weight
+                } else {
+                    1.0
+                };
+                // Tint once per path, as light enters, rather than again on every crossing
+                let color = if !inside && wi.z() < 0.0 {
+                    params.base_color
+                } else {
+                    white
+                };
+
+                (weight * shadowing * color, wi)
+            }
+        };
+
+        Some((
+            attenuation / probability,
+            Ray {
+                origin: rec.point,
+                direction: frame.local(wi),
+                ..ray
+            },
+        ))
+    }
+
+    fn emitted(&self, _ray: Ray, rec: HitRecord) -> Color {
+        if rec.front_face {
+            self.emission.value(rec.u, rec.v, rec.point)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+}
+
+/// Blends two materials, e.g. rust patches on metal, by picking one of them at random for each bounce.
+pub struct MixMaterial {
+    pub first: Box<dyn Material>,
+    pub second: Box<dyn Material>,
+    /// The chance of using `second`, read from the red channel.
+    pub weight: Box<dyn Texture>,
+}
+
+impl MixMaterial {
+    pub fn new(first: Box<dyn Material>, second: Box<dyn Material>, weight: f64) -> Box<Self> {
+        Self::textured(
+            first,
+            second,
+            SolidColor::new(Color::new(weight, weight, weight)),
+        )
+    }
+
+    pub fn textured(
+        first: Box<dyn Material>,
+        second: Box<dyn Material>,
+        weight: Box<dyn Texture>,
+    ) -> Box<Self> {
+        Box::new(Self {
+            first,
+            second,
+            weight,
+        })
+    }
+
+    fn weight(&self, rec: &HitRecord) -> f64 {
+        self.weight
+            .value(rec.u, rec.v, rec.point)
+            .x()
+            .clamp(0.0, 1.0)
+    }
+
+    /// The chance of using `second` at a hit that didn't fall through a hole: where one material has holes, the
+    /// rest of the surface that rays actually hit belongs to the other.
+    fn visible_weight(&self, rec: &HitRecord) -> f64 {
+        let weight = self.weight(rec);
+        let first = (1.0 - weight) * (1.0 - self.first.cut_out_probability(*rec));
+        let second = weight * (1.0 - self.second.cut_out_probability(*rec));
+
+        if first + second > 0.0 {
+            second / (first + second)
+        } else {
+            weight
+        }
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        if rng.gen::<f64>() < self.visible_weight(&rec) {
+            self.second.scatter(ray, rec, rng)
+        } else {
+            self.first.scatter(ray, rec, rng)
+        }
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        lerp(
+            self.first.emitted(ray, rec),
+            self.second.emitted(ray, rec),
+            self.visible_weight(&rec),
+        )
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        let weight = self.visible_weight(&rec);
+
+        (1.0 - weight) * self.first.emitted_spectral(ray, rec)
+            + weight * self.second.emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        let weight = self.weight(&rec);
+
+        (1.0 - weight) * self.first.cut_out_probability(rec)
+            + weight * self.second.cut_out_probability(rec)
+    }
+}
+
+/// Any material under a clear (or tinted) dielectric coat, such as car paint or varnished wood.
+///
+/// Light either reflects off the coat, with the coat's Fresnel reflectance, or passes through it to the base
+/// and back out again, losing what the coat reflects on the way. Light bouncing around inside the coat is
+/// ignored, which makes the base slightly darker than a full simulation would.
+pub struct CoatedMaterial {
+    pub base: Box<dyn Material>,
+    pub refraction_index: f64,
+    pub distribution: Ggx,
+    /// The fraction of each RGB channel that gets through the coat straight on, each way.
+    pub tint: Color,
+}
+
+impl CoatedMaterial {
+    pub fn new(base: Box<dyn Material>, refraction_index: f64, roughness: f64) -> Box<Self> {
+        Self::tinted(base, refraction_index, roughness, Color::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn tinted(
+        base: Box<dyn Material>,
+        refraction_index: f64,
+        roughness: f64,
+        tint: Color,
+    ) -> Box<Self> {
+        Box::new(Self {
+            base,
+            refraction_index,
+            distribution: Ggx::new(roughness, 0.0),
+            tint,
+        })
+    }
+
+    /// How much of the coat's tint is left after crossing it at an angle with cosine `cos_theta`.
+    fn coat_transmittance(&self, cos_theta: f64) -> Color {
+        let path = 1.0 / cos_theta.max(1e-3);
+
+        Color::new(
+            self.tint.x().powf(path),
+            self.tint.y().powf(path),
+            self.tint.z().powf(path),
+        )
+    }
+}
+
+impl Material for CoatedMaterial {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        // The coat only sits on the outside
+        if !rec.front_face {
+            return self.base.scatter(ray, rec, rng);
+        }
+
+        let frame = rec.tangent_frame();
+        let wo = frame.to_local(-ray.direction.unit_vector());
+        let smooth = self.distribution.is_smooth();
+
+        let wm = if smooth {
+            Vector3::new(0.0, 0.0, 1.0)
+        } else {
+            self.distribution.sample_visible_normal(wo, rng)
+        };
+
+        if rng.gen::<f64>() < fresnel_dielectric(wo.dot(&wm), self.refraction_index) {
+            let wi = reflect(-wo, wm);
+            if wi.z() <= 0.0 {
+                return None;
+            }
+
+            let shadowing = if smooth {
+                1.0
+            } else {
+                self.distribution.g(wo, wi) / self.distribution.g1(wo)
+            };
+
+            return Some((
+                Color::new(shadowing, shadowing, shadowing),
+                Ray {
+                    origin: rec.point,
+                    direction: frame.local(wi),
+                    ..ray
+                },
+            ));
+        }
+
+        let (attenuation, scattered) = self.base.scatter(ray, rec, rng)?;
+        let cos_out = scattered.direction.unit_vector().dot(&rec.normal);
+
+        // Light that went into the object rather than back out doesn't cross the coat again
+        if cos_out <= 0.0 {
+            return Some((attenuation * self.coat_transmittance(wo.z()), scattered));
+        }
+
+        let exit = 1.0 - fresnel_dielectric(cos_out, self.refraction_index);
+
+        Some((
+            exit * attenuation * self.coat_transmittance(wo.z()) * self.coat_transmittance(cos_out),
+            scattered,
+        ))
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        self.base.emitted(ray, rec)
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        self.base.emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        self.base.cut_out_probability(rec)
+    }
+}
+
+/// Bends the shading normal of another material using a tangent-space normal map.
+///
+/// The map's RGB encodes a normal in the hit's [tangent frame](HitRecord::tangent_frame), with each channel
+/// remapped from `-1..1` to `0..1`, so `(0.5, 0.5, 1)` leaves the surface unchanged.
+pub struct NormalMap {
+    pub material: Box<dyn Material>,
+    pub map: Box<dyn Texture>,
+    /// Scales how far the normals lean over; 1 uses the map as it is.
+    pub strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(material: Box<dyn Material>, map: Box<dyn Texture>, strength: f64) -> Box<Self> {
+        Box::new(Self {
+            material,
+            map,
+            strength,
+        })
+    }
+
+    fn perturb<'a>(&self, rec: HitRecord<'a>) -> HitRecord<'a> {
+        let encoded = self.map.value(rec.u, rec.v, rec.point);
+        let local = Vector3::new(
+            (2.0 * encoded.x() - 1.0) * self.strength,
+            (2.0 * encoded.y() - 1.0) * self.strength,
+            (2.0 * encoded.z() - 1.0).max(1e-3),
+        );
+
+        HitRecord {
+            normal: rec.tangent_frame().local(local).unit_vector(),
+            ..rec
+        }
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        self.material.scatter(ray, self.perturb(rec), rng)
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        self.material.emitted(ray, rec)
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        self.material.emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        self.material.cut_out_probability(rec)
+    }
+}
+
+/// Bends the shading normal of another material as if the surface were displaced by a height map.
+pub struct BumpMap {
+    pub material: Box<dyn Material>,
+    /// The displacement along the normal, read from the red channel.
+    pub height: Box<dyn Texture>,
+    pub scale: f64,
+}
+
+impl BumpMap {
+    pub fn new(material: Box<dyn Material>, height: Box<dyn Texture>, scale: f64) -> Box<Self> {
+        Box::new(Self {
+            material,
+            height,
+            scale,
+        })
+    }
+
+    fn perturb<'a>(&self, rec: HitRecord<'a>) -> HitRecord<'a> {
+        if rec.dpdu.near_zero() || rec.dpdv.near_zero() {
+            return rec;
+        }
+
+        let height = |u: f64, v: f64, point: Point3| self.height.value(u, v, point).x();
+        let base = height(rec.u, rec.v, rec.point);
+
+        // Finite differences over a small, fixed distance on the surface, so the result doesn't depend on how the
+        // surface happens to be parameterized
+        let step = 1e-3;
+        let du = step / rec.dpdu.length();
+        let dv = step / rec.dpdv.length();
+        let dhdu = (height(rec.u + du, rec.v, rec.point + du * rec.dpdu) - base) / du;
+        let dhdv = (height(rec.u, rec.v + dv, rec.point + dv * rec.dpdv) - base) / dv;
+
+        let dpdu = rec.dpdu + self.scale * dhdu * rec.normal;
+        let dpdv = rec.dpdv + self.scale * dhdv * rec.normal;
+        let normal = Vector3::cross(&dpdu, &dpdv).unit_vector();
+
+        HitRecord {
+            normal: if normal.dot(&rec.normal) < 0.0 {
+                -normal
+            } else {
+                normal
+            },
+            dpdu,
+            dpdv,
+            ..rec
+        }
+    }
+}
+
+impl Material for BumpMap {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        self.material.scatter(ray, self.perturb(rec), rng)
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        self.material.emitted(ray, rec)
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        self.material.emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        self.material.cut_out_probability(rec)
+    }
+}
+
+/// Cuts holes in another material wherever `opacity` is dark, such as between the leaves on a foliage card or the
+/// wires of a fence, so the shape doesn't need modelling.
+pub struct AlphaMask {
+    pub material: Box<dyn Material>,
+    pub opacity: Box<dyn Texture>,
+    /// Cut out below this opacity, or when `None`, let rays through at random in proportion to the transparency.
+    pub threshold: Option<f64>,
+}
+
+impl AlphaMask {
+    /// A mask where grey areas are partly see-through, like a net curtain.
+    pub fn new(material: Box<dyn Material>, opacity: Box<dyn Texture>) -> Box<Self> {
+        Box::new(Self {
+            material,
+            opacity,
+            threshold: None,
+        })
+    }
+
+    /// A mask with hard edges, where anything less opaque than `threshold` is a hole.
+    pub fn cutout(
+        material: Box<dyn Material>,
+        opacity: Box<dyn Texture>,
+        threshold: f64,
+    ) -> Box<Self> {
+        Box::new(Self {
+            material,
+            opacity,
+            threshold: Some(threshold),
+        })
+    }
+}
+
+impl Material for AlphaMask {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        self.material.scatter(ray, rec, rng)
+    }
+
+    fn emitted(&self, ray: Ray, rec: HitRecord) -> Color {
+        self.material.emitted(ray, rec)
+    }
+
+    fn emitted_spectral(&self, ray: Ray, rec: HitRecord) -> f64 {
+        self.material.emitted_spectral(ray, rec)
+    }
+
+    fn cut_out_probability(&self, rec: HitRecord) -> f64 {
+        let opacity = luminance(self.opacity.value(rec.u, rec.v, rec.point));
+        let hole = match self.threshold {
+            Some(threshold) if opacity < threshold => 1.0,
+            Some(_) => 0.0,
+            None => (1.0 - opacity).clamp(0.0, 1.0),
+        };
+
+        1.0 - (1.0 - hole) * (1.0 - self.material.cut_out_probability(rec))
+    }
+}
+
+/// The Henyey–Greenstein phase function, which describes how light scatters inside a participating medium.
+///
+/// `g` ranges from -1 (everything bounces back) through 0 (isotropic) to 1 (everything carries straight on).
+pub struct HenyeyGreenstein {
+    pub albedo: Box<dyn Texture>,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo), g)
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>, g: f64) -> Box<Self> {
+        Box::new(Self {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        })
+    }
+
+    /// Samples the cosine of the angle between the incoming and scattered directions.
+    pub fn sample_cos_theta(g: f64, rng: &mut ThreadRng) -> f64 {
+        let xi = rng.gen::<f64>();
+
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let term = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - term * term) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+
+    pub fn sample_direction(g: f64, direction: Vector3, rng: &mut ThreadRng) -> Vector3 {
+        let cos_theta = Self::sample_cos_theta(g, rng);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+
+        Onb::new(direction).local(Vector3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        // The phase function is sampled exactly, so the only weight left is the albedo
+        Some((
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: Self::sample_direction(self.g, ray.direction, rng),
+                ..ray
+            },
+        ))
+    }
+}
+
+/// A phase function that scatters light equally in all directions.
+pub struct Isotropic {
+    pub albedo: Box<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Box<Self> {
+        Self::textured(SolidColor::new(albedo))
+    }
+
+    pub fn textured(albedo: Box<dyn Texture>) -> Box<Self> {
+        Box::new(Self { albedo })
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        Some((
+            self.albedo.value(rec.u, rec.v, rec.point),
+            Ray {
+                origin: rec.point,
+                direction: random_unit_vector(rng),
+                ..ray
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        hittables::{make_box, HitRecord, Hittable, Sphere},
+        materials::{
+            CoatedMaterial, Conductor, Dielectric, DiffuseLight, HenyeyGreenstein, Lambertian,
+            Material, MixMaterial, OrenNayar, Principled, PrincipledParams, RetroReflective,
+            RoughDielectric, Subsurface, Velvet,
+        },
+        math::{Color, Point3, Ray, Vector3},
+        spectrum::{sample_wavelength, wavelength_to_rgb, Ior},
+        textures::SolidColor,
+        thin_film::{reflectance_for_ray, ThinFilm},
+        utils::{fresnel_conductor, random_cosine_direction},
+    };
+
+    /// The average fraction of light a white version of `material` reflects, for light arriving along `direction`.
+    fn albedo(material: &dyn Material, direction: Vector3) -> f64 {
+        let surface = Lambertian::new(Color::new(1.0, 1.0, 1.0));
+        let rec = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            surface.as_ref(),
+        );
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction);
+
+        let mut rng = thread_rng();
+        let samples = 100_000;
+        let total: f64 = (0..samples)
+            .filter_map(|_| material.scatter(ray, rec, &mut rng))
+            .map(|(attenuation, _)| attenuation.y())
+            .sum();
+
+        total / samples as f64
+    }
+
+    #[test]
+    fn diffuse_and_coated_models_conserve_energy() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let materials: [Box<dyn Material>; 5] = [
+            OrenNayar::new(white, 30.0),
+            RetroReflective::new(white, 0.5, 20.0),
+            Velvet::new(white, 0.5),
+            CoatedMaterial::new(Lambertian::new(white), 1.5, 0.0),
+            CoatedMaterial::new(Lambertian::new(white), 1.5, 0.3),
+        ];
+
+        for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.8, 0.0, -0.6)] {
+            for material in &materials {
+                let albedo = albedo(material.as_ref(), direction);
+
+                assert!(albedo > 0.0 && albedo < 1.01, "{albedo}");
+            }
+        }
+    }
+
+    #[test]
+    fn dielectric_absorbs_along_path_inside() {
+        let absorption = Color::new(0.5, 1.0, 2.0);
+        let thickness = 0.5;
+        let slab = make_box(
+            Point3::new(-100.0, -100.0, 0.0),
+            Point3::new(100.0, 100.0, thickness),
+            Arc::from(Dielectric::absorbing(1.5, absorption) as Box<dyn Material>),
+        );
+        let mut rng = thread_rng();
+        let (mut reflected, mut transmitted) = (0, 0);
+
+        for _ in 0..1_000 {
+            let mut ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0));
+            let mut weight = Color::new(1.0, 1.0, 1.0);
+            let mut crossings = 0;
+
+            while let Some(rec) = slab.hit(ray, 1e-6, f64::INFINITY) {
+                if !rec.front_face {
+                    crossings += 1;
+                }
+                let (attenuation, scattered) = rec.material.scatter(ray, rec, &mut rng).unwrap();
+                weight = weight * attenuation;
+                ray = scattered;
+            }
+
+            // Each trip across the slab absorbs the same fraction, and bouncing off the outside absorbs nothing
+            for channel in 0..3 {
+                let expected = (-absorption[channel] * thickness * crossings as f64).exp();
+
+                assert!((weight[channel] - expected).abs() < 1e-9, "{weight:?}");
+            }
+
+            match (crossings, ray.direction.z() > 0.0) {
+                (0, true) => reflected += 1,
+                (1, false) => transmitted += 1,
+                _ => {}
+            }
+        }
+
+        assert!(reflected > 0 && transmitted > 0);
+    }
+
+    #[test]
+    fn filmed_dielectric_reflects_film_reflectance_when_it_cannot_refract() {
+        let (ior, film) = (Ior::bk7(), ThinFilm::new(300.0, 1.38));
+        let material = Dielectric::new(ior).with_thin_film(film);
+        // Just past the critical angle for blue light leaving the glass, but not for red light
+        let cos_theta = (1.0f64 - 0.658 * 0.658).sqrt();
+        let direction = Vector3::new(0.658, 0.0, -cos_theta);
+        let rec = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+            false,
+            material.as_ref(),
+        );
+        let ray = Ray {
+            wavelength: 400.0,
+            ..Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction)
+        };
+        let reflectance = reflectance_for_ray(ray, |lambda| {
+            film.reflectance_dielectric(cos_theta, ior.at(lambda), 1.0, lambda)
+        });
+        let mut rng = thread_rng();
+
+        // Reflecting isn't a random choice here, so it mustn't be reweighted like one
+        for _ in 0..1_000 {
+            let (attenuation, scattered) = material.scatter(ray, rec, &mut rng).unwrap();
+
+            assert!(scattered.direction.z() > 0.0, "{:?}", scattered.direction);
+            assert!((attenuation - reflectance).near_zero(), "{attenuation:?}");
+        }
+    }
+
+    #[test]
+    fn rough_dielectric_conserves_energy() {
+        for roughness in [0.0, 0.1, 0.3, 0.6, 1.0] {
+            let material = RoughDielectric::new(1.5, roughness);
+
+            for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.8, 0.0, -0.6)] {
+                let albedo = albedo(material.as_ref(), direction);
+
+                assert!(albedo > 0.0 && albedo < 1.01, "{roughness}: {albedo}");
+            }
+        }
+    }
+
+    #[test]
+    fn rough_dielectric_totally_reflects_beyond_critical_angle() {
+        let material = RoughDielectric::new(1.5, 0.0);
+        // Leaving the glass at 60 degrees from the normal, well past the critical angle of about 42 degrees
+        let direction = Vector3::new(0.75f64.sqrt(), 0.0, -0.5);
+        let rec = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+            false,
+            material.as_ref(),
+        );
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction);
+        let mut rng = thread_rng();
+
+        for _ in 0..10_000 {
+            let (attenuation, scattered) = material.scatter(ray, rec, &mut rng).unwrap();
+
+            assert!((attenuation.y() - 1.0).abs() < 1e-9, "{attenuation:?}");
+            assert!(scattered.direction.z() > 0.0, "{:?}", scattered.direction);
+        }
+    }
+
+    #[test]
+    fn smooth_oren_nayar_is_lambertian() {
+        let albedo = albedo(
+            OrenNayar::new(Color::new(1.0, 1.0, 1.0), 0.0).as_ref(),
+            Vector3::new(0.6, 0.0, -0.8),
+        );
+
+        assert!((albedo - 1.0).abs() < 1e-9, "{albedo}");
+    }
+
+    #[test]
+    fn principled_conserves_energy() {
+        for metallic in [0.0, 1.0] {
+            for transmission in [0.0, 1.0] {
+                for clearcoat in [0.0, 1.0] {
+                    let material = Principled::new(PrincipledParams {
+                        base_color: Color::new(1.0, 1.0, 1.0),
+                        metallic,
+                        transmission,
+                        clearcoat,
+                        ..Default::default()
+                    });
+
+                    for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.8, 0.0, -0.6)] {
+                        let albedo = albedo(material.as_ref(), direction);
+
+                        assert!(
+                            albedo > 0.0 && albedo < 1.01,
+                            "{metallic} {transmission} {clearcoat}: {albedo}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn opaque_principled_reflects_from_behind() {
+        let material = Principled::new(PrincipledParams::default());
+        let direction = Vector3::new(0.6, 0.0, -0.8);
+        // The back face of a surface, with the normal turned towards the ray like hittables do
+        let rec = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+            false,
+            material.as_ref(),
+        );
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0) - direction, direction);
+
+        let mut rng = thread_rng();
+        let samples = 100_000;
+        let mut total = 0.0;
+
+        for _ in 0..samples {
+            if let Some((attenuation, scattered)) = material.scatter(ray, rec, &mut rng) {
+                assert!(scattered.direction.z() > 0.0, "{:?}", scattered.direction);
+                total += attenuation.y();
+            }
+        }
+
+        let expected = albedo(material.as_ref(), direction);
+        let albedo = total / samples as f64;
+
+        assert!((albedo - expected).abs() < 0.02, "{albedo} vs {expected}");
+    }
+
+    #[test]
+    fn metallic_principled_matches_conductor() {
+        let (eta, k) = (
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+        );
+
+        for roughness in [0.0, 0.3, 0.7] {
+            let conductor = Conductor::new(eta, k, roughness);
+            let principled = Principled::new(PrincipledParams {
+                base_color: fresnel_conductor(1.0, eta, k),
+                metallic: 1.0,
+                roughness,
+                ..Default::default()
+            });
+
+            for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.8, 0.0, -0.6)] {
+                let expected = albedo(conductor.as_ref(), direction);
+                let albedo = albedo(principled.as_ref(), direction);
+
+                assert!((albedo - expected).abs() < 0.02, "{albedo} vs {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn white_transmissive_principled_passes_all_light() {
+        let material = Principled::new(PrincipledParams {
+            base_color: Color::new(1.0, 1.0, 1.0),
+            roughness: 0.0,
+            transmission: 1.0,
+            ..Default::default()
+        });
+
+        for direction in [Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.8, 0.0, -0.6)] {
+            let albedo = albedo(material.as_ref(), direction);
+
+            assert!((albedo - 1.0).abs() < 0.01, "{albedo}");
+        }
+    }
+
+    #[test]
+    fn mix_averages_albedo_and_emission_by_weight() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let material = MixMaterial::textured(
+            Lambertian::new(white),
+            DiffuseLight::new(white * 2.0),
+            SolidColor::new(white * 0.25),
+        );
+        let direction = Vector3::new(0.0, 0.0, -1.0);
+        let rec = HitRecord::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            material.as_ref(),
+        );
+        let emitted = material.emitted(Ray::new(Point3::new(0.0, 0.0, 1.0), direction), rec);
+
+        // The light reflects nothing and the diffuse surface emits nothing
+        assert!((albedo(material.as_ref(), direction) - 0.75).abs() < 0.01);
+        assert!((emitted.y() - 0.5).abs() < 1e-9, "{emitted:?}");
+    }
+
+    #[test]
+    fn random_walk_reflects_requested_albedo() {
+        let albedo = Color::new(0.8, 0.5, 0.2);
+        let mut rng = thread_rng();
+
+        for mean_free_path in [Color::new(1.0, 1.0, 1.0), Color::new(1.0, 0.75, 0.5)] {
+            // Large enough compared to the free paths to act like a half-infinite slab
+            let sphere = Sphere::new(
+                Point3::new(0.0, 0.0, 0.0),
+                100.0,
+                Subsurface::new(albedo, mean_free_path, 1.0),
+            );
+
+            let samples = 10_000;
+            let mut total = Color::new(0.0, 0.0, 0.0);
+
+            for _ in 0..samples {
+                // Diffuse light arriving at the top of the sphere
+                let direction = -random_cosine_direction(&mut rng);
+                let mut ray = Ray {
+                    wavelength: sample_wavelength(rng.gen()),
+                    ..Ray::new(Point3::new(0.0, 0.0, 100.0) - direction, direction)
+                };
+                let mut weight = Color::new(1.0, 1.0, 1.0);
+
+                for _ in 0..10_000 {
+                    let Some(rec) = sphere.hit(ray, 1e-6, f64::INFINITY) else {
+                        total += weight;
+                        break;
+                    };
+                    let Some((attenuation, scattered)) = rec.material.scatter(ray, rec, &mut rng)
+                    else {
+                        break;
+                    };
+
+                    // Weighted like the integrator does once the path splits into a single wavelength
+                    weight = if scattered.dispersed && !ray.dispersed {
+                        weight * attenuation * wavelength_to_rgb(ray.wavelength)
+                    } else {
+                        weight * attenuation
+                    };
+                    ray = scattered;
+                }
+            }
+
+            let reflectance = total / samples as f64;
+
+            for channel in 0..3 {
+                assert!(
+                    (reflectance[channel] - albedo[channel]).abs() < 0.05,
+                    "{reflectance:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn subsurface_albedo_fit_round_trips() {
+        for albedo in [0.0, 0.1, 0.5, 0.9, 1.0] {
+            let single = Subsurface::single_scattering_albedo(albedo);
+
+            assert!((Subsurface::multiple_scattering_albedo(single) - albedo).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn henyey_greenstein_mean_cosine_is_g() {
+        let mut rng = thread_rng();
+        let samples = 200_000;
+
+        for g in [-0.7, -0.3, 0.0, 0.4, 0.9] {
+            let mean = (0..samples)
+                .map(|_| HenyeyGreenstein::sample_cos_theta(g, &mut rng))
+                .sum::<f64>()
+                / samples as f64;
+
+            assert!((mean - g).abs() < 0.01, "{g}: {mean}");
+        }
     }
 }
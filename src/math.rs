@@ -1,5 +1,7 @@
 use std::ops;
 
+use crate::spectrum::DEFAULT_WAVELENGTH;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vector3(pub f64, pub f64, pub f64);
 
@@ -42,6 +44,22 @@ impl Vector3 {
         *self / self.length()
     }
 
+    pub fn min(&self, other: &Self) -> Self {
+        Vector3(
+            self.0.min(other.0),
+            self.1.min(other.1),
+            self.2.min(other.2),
+        )
+    }
+
+    pub fn max(&self, other: &Self) -> Self {
+        Vector3(
+            self.0.max(other.0),
+            self.1.max(other.1),
+            self.2.max(other.2),
+        )
+    }
+
     pub fn near_zero(&self) -> bool {
         let small = 1e-8;
 
@@ -49,6 +67,19 @@ impl Vector3 {
     }
 }
 
+impl ops::Index<usize> for Vector3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &Self::Output {
+        match axis {
+            0 => &self.0,
+            1 => &self.1,
+            2 => &self.2,
+            _ => panic!("Vector3 index out of range: {axis}"),
+        }
+    }
+}
+
 impl ops::Add for Vector3 {
     type Output = Vector3;
 
@@ -137,6 +168,14 @@ impl ops::Mul<Vector3> for Vector3 {
     }
 }
 
+impl ops::Div<Vector3> for Vector3 {
+    type Output = Vector3;
+
+    fn div(self, rhs: Vector3) -> Self::Output {
+        Vector3(self.0 / rhs.0, self.1 / rhs.1, self.2 / rhs.2)
+    }
+}
+
 pub type Point3 = Vector3;
 pub type Color = Vector3;
 
@@ -207,6 +246,13 @@ mod tests {
         assert_eq!(v, Vector3(2.0, 4.0, 6.0));
     }
 
+    #[test]
+    fn vector_index() {
+        let v = Vector3(1.0, 2.0, 3.0);
+
+        assert_eq!((v[0], v[1], v[2]), (1.0, 2.0, 3.0));
+    }
+
     #[test]
     fn vector_neg() {
         assert_eq!(-Vector3(1.0, 2.0, 3.0), Vector3(-1.0, -2.0, -3.0));
@@ -217,14 +263,63 @@ mod tests {
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vector3,
+    /// The instant the ray was sent out at, used to place moving objects.
+    pub time: f64,
+    /// The path's hero wavelength, which only matters once it passes through something dispersive.
+    pub wavelength: f64,
+    /// Whether the path has been split by dispersion, after which it only carries light at `wavelength`.
+    pub dispersed: bool,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vector3) -> Self {
-        Ray { origin, direction }
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    pub fn with_time(origin: Point3, direction: Vector3, time: f64) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+            wavelength: DEFAULT_WAVELENGTH,
+            dispersed: false,
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
         self.origin + self.direction * t
     }
 }
+
+/// An orthonormal basis built around a direction `w`, used to turn locally sampled directions into world space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Onb {
+    pub u: Vector3,
+    pub v: Vector3,
+    pub w: Vector3,
+}
+
+impl Onb {
+    pub fn new(w: Vector3) -> Self {
+        let w = w.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vector3::cross(&w, &a).unit_vector();
+        let u = Vector3::cross(&v, &w);
+
+        Onb { u, v, w }
+    }
+
+    /// Converts local coordinates (`z` along `w`) into world space.
+    pub fn local(&self, a: Vector3) -> Vector3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    /// Converts a world space vector into local coordinates.
+    pub fn to_local(&self, a: Vector3) -> Vector3 {
+        Vector3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
@@ -0,0 +1,156 @@
+//! The GGX (Trowbridge–Reitz) microfacet distribution, shared by the rough materials.
+//!
+//! Everything here works in a local shading frame where the surface normal is `+z`.
+
+use std::f64::consts::PI;
+
+use rand::{rngs::ThreadRng, Rng};
+
+use crate::math::Vector3;
+
+/// Turns a perceptual roughness in `0..=1` and an anisotropy in `0..1` into GGX widths along `x` and `y`.
+pub fn roughness_to_alpha(roughness: f64, anisotropy: f64) -> (f64, f64) {
+    let alpha = roughness.clamp(0.0, 1.0).powi(2).max(1e-4);
+    let aspect = (1.0 - 0.9 * anisotropy.clamp(0.0, 1.0)).sqrt();
+
+    (alpha / aspect, alpha * aspect)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ggx {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl Ggx {
+    pub fn new(roughness: f64, anisotropy: f64) -> Self {
+        let (alpha_x, alpha_y) = roughness_to_alpha(roughness, anisotropy);
+
+        Self { alpha_x, alpha_y }
+    }
+
+    /// Whether the surface is so smooth that it should be treated as a perfect mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// The density of microfacets facing `wm`.
+    pub fn d(&self, wm: Vector3) -> f64 {
+        if wm.z() <= 0.0 {
+            return 0.0;
+        }
+
+        let x = wm.x() / self.alpha_x;
+        let y = wm.y() / self.alpha_y;
+        let e = x * x + y * y + wm.z() * wm.z();
+
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    /// Smith's auxiliary function, the ratio of hidden to visible microfacet area seen from `w`.
+    pub fn lambda(&self, w: Vector3) -> f64 {
+        let z2 = w.z() * w.z();
+        if z2 == 0.0 {
+            return f64::INFINITY;
+        }
+
+        let alpha2_tan2 =
+            (self.alpha_x.powi(2) * w.x().powi(2) + self.alpha_y.powi(2) * w.y().powi(2)) / z2;
+
+        ((1.0 + alpha2_tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// The fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// The fraction of microfacets visible from both `wo` and `wi` (height-correlated Smith).
+    pub fn g(&self, wo: Vector3, wi: Vector3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// The density of microfacet normals `wm` as seen from `wo`, which is what `sample_visible_normal` draws from.
+    pub fn visible_normal_pdf(&self, wo: Vector3, wm: Vector3) -> f64 {
+        if wo.z() <= 0.0 {
+            return 0.0;
+        }
+
+        self.g1(wo) * wo.dot(&wm).max(0.0) * self.d(wm) / wo.z()
+    }
+
+    /// Samples a microfacet normal in proportion to how much of it is visible from `wo` (Heitz 2018).
+    pub fn sample_visible_normal(&self, wo: Vector3, rng: &mut ThreadRng) -> Vector3 {
+        // Stretch the view direction into the configuration where the distribution is a hemisphere
+        let vh = Vector3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+
+        let length2 = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if length2 > 0.0 {
+            Vector3::new(-vh.y(), vh.x(), 0.0) / length2.sqrt()
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = Vector3::cross(&vh, &t1);
+
+        // Sample the projected half-disk facing the view direction
+        let r = rng.gen::<f64>().sqrt();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // Unstretch back to the original roughness
+        Vector3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::{math::Vector3, microfacet::Ggx};
+
+    #[test]
+    fn projected_area_integrates_to_one() {
+        // The microfacets' projected area must add up to the macro surface's: ∫ D(m) cos(m) dm = 1
+        let ggx = Ggx::new(0.5, 0.3);
+        let mut rng = thread_rng();
+        let samples = 200_000;
+
+        let estimate = (0..samples)
+            .map(|_| {
+                let z: f64 = rng.gen();
+                let phi = 2.0 * PI * rng.gen::<f64>();
+                let r = (1.0 - z * z).sqrt();
+                let wm = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+
+                ggx.d(wm) * wm.z() * 2.0 * PI
+            })
+            .sum::<f64>()
+            / samples as f64;
+
+        assert!((estimate - 1.0).abs() < 0.05, "{estimate}");
+    }
+
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        let ggx = Ggx::new(0.8, 0.0);
+        let wo = Vector3::new(0.6, 0.0, 0.8);
+        let mut rng = thread_rng();
+
+        for _ in 0..1000 {
+            let wm = ggx.sample_visible_normal(wo, &mut rng);
+
+            assert!(wm.z() > 0.0 && wo.dot(&wm) >= -1e-9);
+        }
+    }
+}
@@ -0,0 +1,1146 @@
+//! Canonical test scenes, each bundling a world with a camera that frames it.
+
+use std::sync::Arc;
+
+use rand::{Rng, SeedableRng};
+
+use crate::{
+    aabb::Aabb,
+    camera::{
+        Aperture, Camera, Exposure, FieldOfView, OrthographicCamera, PanoramicCamera,
+        PerspectiveCamera, Projection, RealisticCamera, StereoCamera, StereoLayout,
+    },
+    fog::Fog,
+    hittables::{
+        make_box, AnimatedInstance, Background, BvhNode, ConstantMedium, Hittable, HittableList,
+        Instance, Quad, Sphere,
+    },
+    lens::LensSystem,
+    materials::{
+        AlphaMask, BumpMap, CoatedMaterial, Conductor, Dielectric, DiffuseLight, HenyeyGreenstein,
+        Isotropic, Lambertian, Material, Metal, MixMaterial, OrenNayar, Principled,
+        PrincipledParams, RetroReflective, RoughDielectric, Subsurface, Velvet,
+    },
+    math::{Color, Point3, Vector3},
+    spectrum::{Illuminant, Ior, Spectrum},
+    textures::{CheckerTexture, NoiseTexture, Perlin, SolidColor, UvChecker},
+    thin_film::ThinFilm,
+    transform::{AnimatedTransform, Keyframe, Quaternion, Transform},
+    volumes::{HeterogeneousMedium, VoxelGrid},
+};
+
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: Arc<dyn Camera>,
+}
+
+pub type SceneBuilder = fn() -> Scene;
+
+/// Every preset by name, for picking one from the command line or iterating over them in benchmarks.
+pub const PRESETS: [(&str, SceneBuilder); 23] = [
+    ("book1_final", book1_final),
+    ("motion_blur", motion_blur),
+    ("cornell_box", cornell_box),
+    ("cornell_smoke", cornell_smoke),
+    ("clouds_and_fire", clouds_and_fire),
+    ("materials_showcase", materials_showcase),
+    ("glass_caustics", glass_caustics),
+    ("texture_test", texture_test),
+    ("many_lights", many_lights),
+    ("forest", forest),
+    ("foggy_forest", foggy_forest),
+    ("light_spectra", light_spectra),
+    ("subsurface", subsurface),
+    ("cutouts", cutouts),
+    ("isometric", isometric),
+    ("panorama", panorama),
+    ("fisheye", fisheye),
+    ("stereo", stereo),
+    ("stereo_panorama", stereo_panorama),
+    ("bokeh", bokeh),
+    ("lens", lens),
+    ("overcast", overcast),
+    ("tilt_shift", tilt_shift),
+];
+
+pub fn by_name(name: &str) -> Option<SceneBuilder> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, build)| *build)
+}
+
+fn shared(material: Box<dyn Material>) -> Arc<dyn Material> {
+    Arc::from(material)
+}
+
+/// The random field of small spheres from the cover of Ray Tracing in One Weekend.
+pub fn book1_final() -> Scene {
+    let mut world = HittableList::new();
+
+    let ground_material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_material,
+    ));
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(10);
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let choose_mat = rng.gen::<f64>();
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material: Box<dyn Material> = if choose_mat < 0.8 {
+                    // diffuse
+                    let albedo = Color::new(rng.gen(), rng.gen(), rng.gen())
+                        * Color::new(rng.gen(), rng.gen(), rng.gen());
+                    Lambertian::new(albedo)
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let albedo = Color::new(
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                        rng.gen_range(0.5..1.0),
+                    );
+                    let fuzz = rng.gen_range(0.0..0.5);
+                    Metal::new(albedo, fuzz)
+                } else {
+                    // glass
+                    Dielectric::new(1.5)
+                };
+
+                world.add(Sphere::new(center, 0.2, sphere_material));
+            }
+        }
+    }
+
+    let material1 = Dielectric::new(1.5);
+    world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, material1));
+
+    let material2 = Lambertian::new(Color::new(0.4, 0.2, 0.1));
+    world.add(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, material2));
+
+    let material3 = Metal::new(Color::new(0.7, 0.6, 0.5), 0.0);
+    world.add(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, material3));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        20.0,
+        16.0 / 9.0,
+        0.1,
+        10.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Bouncing spheres and a tumbling box, photographed with the shutter open from time 0 to 1.
+pub fn motion_blur() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::textured(CheckerTexture::from_colors(
+            0.5,
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(2);
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for a in -6..6 {
+        for b in -6..6 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            let albedo = Color::new(rng.gen(), rng.gen(), rng.gen())
+                * Color::new(rng.gen(), rng.gen(), rng.gen());
+
+            objects.push(Sphere::moving(
+                center,
+                center + Vector3::new(0.0, rng.gen_range(0.0..0.5), 0.0),
+                0.0,
+                1.0,
+                0.2,
+                Lambertian::new(albedo),
+            ));
+        }
+    }
+
+    world.add(BvhNode::new(objects, 0.0, 1.0));
+
+    let crate_box = make_box(
+        Point3::new(-0.5, -0.5, -0.5),
+        Point3::new(0.5, 0.5, 0.5),
+        shared(Lambertian::new(Color::new(0.8, 0.3, 0.1))),
+    );
+    world.add(AnimatedInstance::new(
+        Arc::new(crate_box),
+        AnimatedTransform::new(
+            Keyframe {
+                translation: Vector3::new(1.5, 1.0, 0.0),
+                ..Keyframe::default()
+            },
+            Keyframe {
+                rotation: Quaternion::from_axis_angle(Vector3::new(1.0, 1.0, 0.0), 40.0),
+                translation: Vector3::new(1.9, 1.2, 0.0),
+                ..Keyframe::default()
+            },
+            0.0,
+            1.0,
+        ),
+    ));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        20.0,
+        16.0 / 9.0,
+        0.0,
+        10.0,
+    )
+    .with_shutter(0.0, 1.0);
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The walls and ceiling light of the Cornell box, along with its white material for reuse.
+fn cornell_room() -> (HittableList, Arc<dyn Material>) {
+    let mut world = HittableList::new();
+    world.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+
+    let red = Lambertian::new(Color::new(0.65, 0.05, 0.05));
+    let white = shared(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green = Lambertian::new(Color::new(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new(Color::new(15.0, 15.0, 15.0));
+
+    world.add(Quad::new(
+        Point3::new(555.0, 0.0, 0.0),
+        Vector3::new(0.0, 555.0, 0.0),
+        Vector3::new(0.0, 0.0, 555.0),
+        green,
+    ));
+    world.add(Quad::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 555.0, 0.0),
+        Vector3::new(0.0, 0.0, 555.0),
+        red,
+    ));
+    world.add(Quad::new(
+        Point3::new(343.0, 554.0, 332.0),
+        Vector3::new(-130.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -105.0),
+        light,
+    ));
+    world.add(Quad::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(555.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 555.0),
+        Box::new(white.clone()),
+    ));
+    world.add(Quad::new(
+        Point3::new(555.0, 555.0, 555.0),
+        Vector3::new(-555.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, -555.0),
+        Box::new(white.clone()),
+    ));
+    world.add(Quad::new(
+        Point3::new(0.0, 0.0, 555.0),
+        Vector3::new(555.0, 0.0, 0.0),
+        Vector3::new(0.0, 555.0, 0.0),
+        Box::new(white.clone()),
+    ));
+
+    (world, white)
+}
+
+/// The tall and short boxes standing in the Cornell box.
+fn cornell_boxes(material: Arc<dyn Material>) -> [Box<dyn Hittable>; 2] {
+    let tall_box = make_box(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 330.0, 165.0),
+        material.clone(),
+    );
+    let short_box = make_box(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(165.0, 165.0, 165.0),
+        material,
+    );
+
+    [
+        Instance::new(
+            Arc::new(tall_box),
+            Transform::rotation_y(15.0)
+                .then(&Transform::translation(Vector3::new(265.0, 0.0, 295.0))),
+        ),
+        Instance::new(
+            Arc::new(short_box),
+            Transform::rotation_y(-18.0)
+                .then(&Transform::translation(Vector3::new(130.0, 0.0, 65.0))),
+        ),
+    ]
+}
+
+fn cornell_camera() -> Arc<dyn Camera> {
+    Arc::new(PerspectiveCamera::new(
+        Point3::new(278.0, 278.0, -800.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        40.0,
+        1.0,
+        0.0,
+        10.0,
+    ))
+}
+
+/// The classic Cornell box: a white room with a red and a green wall, lit by a ceiling light.
+pub fn cornell_box() -> Scene {
+    let (mut world, white) = cornell_room();
+
+    for object in cornell_boxes(white) {
+        world.add(object);
+    }
+
+    Scene {
+        world,
+        camera: cornell_camera(),
+    }
+}
+
+/// The Cornell box with its two boxes replaced by dark smoke and a forward-scattering white fog.
+pub fn cornell_smoke() -> Scene {
+    let (mut world, white) = cornell_room();
+    let [tall_box, short_box] = cornell_boxes(white);
+
+    world.add(ConstantMedium::new(
+        tall_box,
+        0.01,
+        Isotropic::new(Color::new(0.0, 0.0, 0.0)),
+    ));
+    world.add(ConstantMedium::new(
+        short_box,
+        0.01,
+        HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), 0.6),
+    ));
+
+    Scene {
+        world,
+        camera: cornell_camera(),
+    }
+}
+
+/// A procedural noise cloud drifting over a glowing, emissive fireball.
+pub fn clouds_and_fire() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(Color::new(0.4, 0.4, 0.4)),
+    ));
+
+    world.add(HeterogeneousMedium::new(
+        VoxelGrid::cloud(64, 4.0),
+        Aabb::new(Point3::new(-4.0, 2.0, -2.0), Point3::new(0.0, 5.0, 2.0)),
+        3.0,
+        Color::new(0.95, 0.95, 0.95),
+        0.6,
+    ));
+
+    let noise = Perlin::new();
+    let fireball = VoxelGrid::from_fn([64; 3], |point| {
+        let radius = (point - Point3::new(0.5, 0.5, 0.5)).length() * 2.0;
+        let heat = (1.0 - radius + 0.4 * noise.turbulence(point * 6.0, 4)).max(0.0);
+
+        (heat * 2.0, Color::new(4.0, 1.2, 0.2) * heat * heat)
+    });
+    let mut fireball = HeterogeneousMedium::new(
+        fireball,
+        Aabb::new(Point3::new(0.5, 0.0, -1.5), Point3::new(3.5, 3.0, 1.5)),
+        2.0,
+        Color::new(0.3, 0.3, 0.3),
+        0.0,
+    );
+    fireball.emission_scale = 4.0;
+    world.add(fireball);
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 3.0, 14.0),
+        Point3::new(0.0, 2.5, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        40.0,
+        16.0 / 9.0,
+        0.0,
+        14.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// One sphere of each material, in rows of five on a checkered floor, for comparing their looks side by side.
+pub fn materials_showcase() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::textured(CheckerTexture::from_colors(
+            0.5,
+            Color::new(0.2, 0.2, 0.2),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Lambertian::new(Color::new(0.1, 0.2, 0.5)),
+        Metal::new(Color::new(0.8, 0.8, 0.8), 0.0),
+        Metal::new(Color::new(0.8, 0.6, 0.2), 0.3),
+        Dielectric::new(1.5),
+        DiffuseLight::new(Color::new(4.0, 2.0, 1.0)),
+        Conductor::gold(0.2),
+        Conductor::copper(0.4),
+        Conductor::aluminium(0.1),
+        Conductor::silver(0.0),
+        Conductor::anisotropic(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            0.4,
+            0.9,
+        ),
+        RoughDielectric::new(1.5, 0.3),
+        Dielectric::tinted(1.5, Color::new(0.8, 0.1, 0.2), 1.0),
+        Principled::new(PrincipledParams {
+            base_color: Color::new(0.7, 0.05, 0.05),
+            roughness: 0.6,
+            clearcoat: 1.0,
+            ..PrincipledParams::default()
+        }),
+        Principled::new(PrincipledParams {
+            base_color: Color::new(0.2, 0.1, 0.4),
+            roughness: 1.0,
+            sheen: 1.0,
+            ..PrincipledParams::default()
+        }),
+        Principled::new(PrincipledParams {
+            base_color: Color::new(0.9, 0.95, 1.0),
+            roughness: 0.2,
+            transmission: 1.0,
+            ..PrincipledParams::default()
+        }),
+        OrenNayar::new(Color::new(0.7, 0.45, 0.3), 30.0),
+        RetroReflective::new(Color::new(0.8, 0.8, 0.2), 0.6, 20.0),
+        Velvet::new(Color::new(0.9, 0.2, 0.4), 0.4),
+        // Car paint
+        CoatedMaterial::new(Lambertian::new(Color::new(0.5, 0.02, 0.02)), 1.5, 0.0),
+        // Copper with patches of green patina
+        MixMaterial::textured(
+            Conductor::copper(0.3),
+            Lambertian::new(Color::new(0.25, 0.55, 0.45)),
+            NoiseTexture::new(6.0),
+        ),
+        // Soap bubble
+        Dielectric::new(1.0).with_thin_film(ThinFilm::new(500.0, 1.33)),
+        // Steel tempered blue by a layer of oxide
+        Conductor::new(
+            Color::new(2.87, 2.91, 2.55),
+            Color::new(3.08, 2.93, 2.79),
+            0.05,
+        )
+        .with_thin_film(ThinFilm::new(70.0, 2.4)),
+    ];
+
+    for (i, material) in materials.into_iter().enumerate() {
+        let (row, column) = (i / 5, i % 5);
+        let center = Point3::new((column as f64 - 2.0) * 2.2, 1.0, -(row as f64) * 2.4);
+
+        world.add(Sphere::new(center, 1.0, material));
+    }
+
+    // A hollow glass bubble in front of the rows
+    world.add(Sphere::new(
+        Point3::new(0.0, 0.5, 2.5),
+        0.5,
+        Dielectric::new(1.5),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 0.5, 2.5),
+        -0.45,
+        Dielectric::new(1.5),
+    ));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 8.0, 9.0),
+        Point3::new(0.0, 0.5, -3.0),
+        Vector3(0.0, 1.0, 0.0),
+        50.0,
+        16.0 / 9.0,
+        0.0,
+        14.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Glass objects on a white floor under a single small light, focusing bright caustics onto the floor. The
+/// flint and diamond spheres disperse light into rainbow fringes.
+pub fn glass_caustics() -> Scene {
+    let mut world = HittableList::new();
+    world.background = Background::Solid(Color::new(0.01, 0.01, 0.02));
+
+    world.add(Quad::new(
+        Point3::new(-10.0, 0.0, -10.0),
+        Vector3::new(20.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 20.0),
+        Lambertian::new(Color::new(0.8, 0.8, 0.8)),
+    ));
+
+    world.add(Sphere::new(
+        Point3::new(-1.2, 1.0, 0.0),
+        1.0,
+        Dielectric::new(Ior::flint()),
+    ));
+    world.add(Sphere::new(
+        Point3::new(1.2, 0.6, 0.5),
+        0.6,
+        Dielectric::new(Ior::diamond()),
+    ));
+
+    let glass = shared(Dielectric::tinted(1.5, Color::new(0.3, 0.8, 0.5), 1.0));
+    world.add(Instance::new(
+        Arc::new(make_box(
+            Point3::new(-0.4, 0.0, -0.4),
+            Point3::new(0.4, 1.6, 0.4),
+            glass,
+        )),
+        Transform::rotation_y(30.0).then(&Transform::translation(Vector3::new(0.8, 0.0, -1.5))),
+    ));
+
+    world.add(Sphere::new(
+        Point3::new(0.0, 6.0, 0.0),
+        0.5,
+        DiffuseLight::new(Color::new(60.0, 60.0, 60.0)),
+    ));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 4.0, 8.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        35.0,
+        16.0 / 9.0,
+        0.0,
+        9.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Procedural and UV-mapped textures, for checking texture lookups and sphere/quad parameterizations.
+pub fn texture_test() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::textured(NoiseTexture::new(4.0)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Lambertian::textured(CheckerTexture::from_colors(
+            0.25,
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Lambertian::textured(UvChecker::new(
+            16,
+            Color::new(0.9, 0.2, 0.2),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+    world.add(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        BumpMap::new(
+            Lambertian::textured(NoiseTexture::new(8.0)),
+            NoiseTexture::new(8.0),
+            0.1,
+        ),
+    ));
+    world.add(Quad::new(
+        Point3::new(-3.0, 0.0, -2.0),
+        Vector3::new(6.0, 0.0, 0.0),
+        Vector3::new(0.0, 3.0, 0.0),
+        Lambertian::textured(UvChecker::new(
+            8,
+            Color::new(0.1, 0.1, 0.5),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 2.0, 9.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        35.0,
+        16.0 / 9.0,
+        0.0,
+        9.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// A dark room lit only by hundreds of small coloured emitters.
+pub fn many_lights() -> Scene {
+    let mut world = HittableList::new();
+    world.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+
+    world.add(Quad::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 40.0),
+        Lambertian::new(Color::new(0.6, 0.6, 0.6)),
+    ));
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(27);
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for a in -12..12 {
+        for b in -12..12 {
+            let center = Point3::new(
+                a as f64 + rng.gen_range(0.0..0.8),
+                rng.gen_range(0.2..3.0),
+                b as f64 + rng.gen_range(0.0..0.8),
+            );
+            let color = Color::new(rng.gen(), rng.gen(), rng.gen()) * 4.0;
+
+            objects.push(Sphere::new(center, 0.1, DiffuseLight::new(color)));
+        }
+    }
+
+    for _ in 0..30 {
+        let center = Point3::new(rng.gen_range(-10.0..10.0), 0.6, rng.gen_range(-10.0..10.0));
+        objects.push(Sphere::new(
+            center,
+            0.6,
+            Metal::new(Color::new(0.8, 0.8, 0.8), rng.gen_range(0.0..0.3)),
+        ));
+    }
+
+    world.add(BvhNode::new(objects, 0.0, 0.0));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 6.0, 18.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        45.0,
+        16.0 / 9.0,
+        0.0,
+        18.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Tens of thousands of instances of one shared tree model, for stressing the BVH and instancing.
+pub fn forest() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Sphere::new(
+        Point3::new(0.0, -10000.0, 0.0),
+        10000.0,
+        Lambertian::new(Color::new(0.35, 0.3, 0.2)),
+    ));
+
+    let mut tree = HittableList::new();
+    let bark = shared(Lambertian::new(Color::new(0.3, 0.2, 0.1)));
+    let leaves = shared(Lambertian::new(Color::new(0.1, 0.4, 0.1)));
+
+    for piece in make_box(
+        Point3::new(-0.1, 0.0, -0.1),
+        Point3::new(0.1, 1.0, 0.1),
+        bark,
+    )
+    .objects
+    {
+        tree.add(piece);
+    }
+    for (height, radius) in [(1.0, 0.7), (1.6, 0.55), (2.1, 0.4), (2.5, 0.25)] {
+        tree.add(Sphere::new(
+            Point3::new(0.0, height, 0.0),
+            radius,
+            Box::new(leaves.clone()),
+        ));
+    }
+    let tree: Arc<dyn Hittable> =
+        Arc::from(BvhNode::new(tree.objects, 0.0, 0.0) as Box<dyn Hittable>);
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(42);
+    let mut trees: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for a in -100..100 {
+        for b in -100..100 {
+            let position = Vector3::new(
+                a as f64 * 2.0 + rng.gen_range(-0.8..0.8),
+                0.0,
+                b as f64 * 2.0 + rng.gen_range(-0.8..0.8),
+            );
+
+            // Leave a clearing around the camera
+            if position.length() < 4.0 {
+                continue;
+            }
+
+            let transform = Transform::uniform_scale(rng.gen_range(0.7..1.4))
+                .then(&Transform::rotation_y(rng.gen_range(0.0..360.0)))
+                .then(&Transform::translation(position));
+
+            trees.push(Instance::new(tree.clone(), transform));
+        }
+    }
+
+    world.add(BvhNode::new(trees, 0.0, 0.0));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 3.0, 0.0),
+        Point3::new(20.0, 1.0, 20.0),
+        Vector3(0.0, 1.0, 0.0),
+        50.0,
+        16.0 / 9.0,
+        0.0,
+        20.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The forest on a misty morning, with height fog pooling between the trees.
+pub fn foggy_forest() -> Scene {
+    let mut scene = forest();
+    scene.world.fog = Some(Fog::height(Color::new(0.9, 0.9, 0.9), 0.15, 1.2, 0.0));
+
+    scene
+}
+
+/// Matching spheres under four lights with different spectra: a candle-like 1900 K black body, tungsten
+/// (illuminant A), daylight (D65) and a triphosphor fluorescent tube (F11). With the spectral integrator, the
+/// coloured spheres shift with each light's spectrum rather than just its RGB colour.
+pub fn light_spectra() -> Scene {
+    let mut world = HittableList::new();
+    world.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+
+    world.add(Quad::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 40.0),
+        Lambertian::new(Color::new(0.73, 0.73, 0.73)),
+    ));
+
+    let lights = [
+        Spectrum::blackbody(1900.0),
+        Spectrum::illuminant(Illuminant::A),
+        Spectrum::illuminant(Illuminant::D65),
+        Spectrum::illuminant(Illuminant::F11),
+    ];
+
+    for (i, spectrum) in lights.into_iter().enumerate() {
+        let x = (i as f64 - 1.5) * 3.0;
+
+        world.add(Sphere::new(
+            Point3::new(x, 3.5, 0.0),
+            0.4,
+            DiffuseLight::spectral(spectrum, 30.0),
+        ));
+        world.add(Sphere::new(
+            Point3::new(x - 0.55, 0.5, 0.0),
+            0.5,
+            Lambertian::new(Color::new(0.8, 0.8, 0.8)),
+        ));
+        world.add(Sphere::new(
+            Point3::new(x + 0.55, 0.5, 0.0),
+            0.5,
+            Lambertian::new(Color::new(0.1, 0.5, 0.7)),
+        ));
+    }
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 3.0, 10.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        45.0,
+        16.0 / 9.0,
+        0.0,
+        10.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Translucent spheres of skin, marble, milk and wax, lit from behind so light shines through their edges, next to
+/// an opaque sphere for comparison. Light travels different distances per colour in the measured materials, which
+/// splits paths like dispersion does, so they need more samples than the wax to lose their colour noise.
+pub fn subsurface() -> Scene {
+    let mut world = HittableList::new();
+    world.background = Background::Solid(Color::new(0.02, 0.02, 0.03));
+
+    world.add(Quad::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 40.0),
+        Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+    ));
+
+    // A large soft light in front and a small bright one behind
+    world.add(Quad::new(
+        Point3::new(-3.0, 6.0, 3.0),
+        Vector3::new(6.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 3.0),
+        DiffuseLight::new(Color::new(4.0, 4.0, 4.0)),
+    ));
+    world.add(Sphere::new(
+        Point3::new(0.0, 2.5, -4.0),
+        0.6,
+        DiffuseLight::new(Color::new(30.0, 28.0, 25.0)),
+    ));
+
+    // Measured materials, enlarged so that a millimetre is a quarter of a unit
+    let materials: [Box<dyn Material>; 5] = [
+        Lambertian::new(Color::new(0.8, 0.5, 0.4)),
+        Subsurface::skin(0.25),
+        Subsurface::marble(0.25),
+        Subsurface::milk(0.25),
+        Subsurface::new(Color::new(0.9, 0.6, 0.3), Color::new(0.3, 0.3, 0.3), 1.45),
+    ];
+
+    for (i, material) in materials.into_iter().enumerate() {
+        let x = (i as f64 - 2.0) * 1.8;
+
+        world.add(Sphere::new(Point3::new(x, 0.8, 0.0), 0.8, material));
+    }
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 2.0, 9.0),
+        Point3::new(0.0, 0.8, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        40.0,
+        16.0 / 9.0,
+        0.0,
+        9.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// Shapes cut out of flat quads by alpha masks: a lattice fence, clumps of foliage cards and a half-transparent
+/// curtain, with spheres behind them showing through the holes.
+pub fn cutouts() -> Scene {
+    let mut world = HittableList::new();
+
+    world.add(Quad::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vector3::new(40.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 40.0),
+        Lambertian::new(Color::new(0.4, 0.35, 0.25)),
+    ));
+
+    for (i, color) in [
+        Color::new(0.8, 0.2, 0.1),
+        Color::new(0.9, 0.8, 0.2),
+        Color::new(0.2, 0.3, 0.8),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        world.add(Sphere::new(
+            Point3::new((i as f64 - 1.0) * 2.2, 0.8, -2.5),
+            0.8,
+            Lambertian::new(color),
+        ));
+    }
+
+    // A fence with square holes
+    world.add(Quad::new(
+        Point3::new(-4.0, 0.0, -1.0),
+        Vector3::new(5.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.2, 0.0),
+        AlphaMask::cutout(
+            Lambertian::new(Color::new(0.5, 0.35, 0.2)),
+            UvChecker::new(24, Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0)),
+            0.5,
+        ),
+    ));
+
+    // A bush made of crossed cards, with the leaves' gaps cut out of a noise pattern
+    let leaves = shared(AlphaMask::cutout(
+        Lambertian::new(Color::new(0.15, 0.45, 0.1)),
+        NoiseTexture::new(12.0),
+        0.6,
+    ));
+    for angle in [0.0f64, 60.0, 120.0] {
+        let (sin, cos) = angle.to_radians().sin_cos();
+
+        world.add(Quad::new(
+            Point3::new(-2.8 - 0.9 * cos, 0.0, 0.5 - 0.9 * sin),
+            Vector3::new(1.8 * cos, 0.0, 1.8 * sin),
+            Vector3::new(0.0, 1.6, 0.0),
+            Box::new(leaves.clone()),
+        ));
+    }
+
+    // A net curtain that lets through a little over half of the light
+    world.add(Quad::new(
+        Point3::new(1.5, 0.0, -0.5),
+        Vector3::new(2.5, 0.0, 0.0),
+        Vector3::new(0.0, 2.2, 0.0),
+        AlphaMask::new(
+            Lambertian::new(Color::new(0.9, 0.9, 0.9)),
+            SolidColor::new(Color::new(0.45, 0.45, 0.45)),
+        ),
+    ));
+
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 2.0, 7.0),
+        Point3::new(0.0, 0.8, -1.0),
+        Vector3(0.0, 1.0, 0.0),
+        45.0,
+        16.0 / 9.0,
+        0.0,
+        8.0,
+    );
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The materials showcase seen from an isometric angle through an orthographic camera, so the rows of spheres stay
+/// the same size all the way to the back.
+pub fn isometric() -> Scene {
+    let center = Point3::new(0.0, 0.5, -4.0);
+    let camera = OrthographicCamera::new(
+        center + Vector3::new(20.0, 20.0, 20.0),
+        center,
+        Vector3(0.0, 1.0, 0.0),
+        16.0,
+        9.0,
+    );
+
+    Scene {
+        world: materials_showcase().world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// A 360 degree equirectangular panorama from among the showcase spheres, which can be used as an environment map.
+pub fn panorama() -> Scene {
+    let camera = PanoramicCamera::new(
+        Point3::new(1.1, 1.0, -1.2),
+        Point3::new(1.1, 1.0, -10.0),
+        Vector3(0.0, 1.0, 0.0),
+        Projection::full_sphere(),
+    );
+
+    Scene {
+        world: materials_showcase().world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The forest seen through a 180 degree fisheye lens pointing up at the treetops.
+pub fn fisheye() -> Scene {
+    let camera = PanoramicCamera::new(
+        Point3::new(0.0, 0.5, 0.0),
+        Point3::new(0.0, 10.0, 0.0),
+        Vector3(0.0, 0.0, -1.0),
+        Projection::EquisolidFisheye { fov: 180.0 },
+    );
+
+    Scene {
+        world: forest().world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The materials showcase as a side-by-side stereo pair, converging on the middle rows.
+pub fn stereo() -> Scene {
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 4.0, 8.0),
+        Point3::new(0.0, 0.5, -3.0),
+        Vector3(0.0, 1.0, 0.0),
+        60.0,
+        1.0,
+        0.0,
+        12.0,
+    );
+
+    Scene {
+        world: materials_showcase().world,
+        camera: Arc::new(StereoCamera::perspective(
+            camera,
+            0.3,
+            12.0,
+            StereoLayout::SideBySide,
+        )),
+    }
+}
+
+/// The panorama from among the showcase spheres in omni-directional stereo, with the left eye above the right,
+/// as VR players expect.
+pub fn stereo_panorama() -> Scene {
+    let camera = PanoramicCamera::new(
+        Point3::new(1.1, 1.0, -1.2),
+        Point3::new(1.1, 1.0, -10.0),
+        Vector3(0.0, 1.0, 0.0),
+        Projection::full_sphere(),
+    );
+
+    Scene {
+        world: materials_showcase().world,
+        camera: Arc::new(StereoCamera::omnidirectional(
+            camera,
+            0.3,
+            f64::INFINITY,
+            StereoLayout::OverUnder,
+        )),
+    }
+}
+
+/// The many lights, thrown out of focus through a six-bladed aperture so each one turns into a hexagon, pinched
+/// into cat's eyes towards the corners.
+pub fn bokeh() -> Scene {
+    let camera = PerspectiveCamera::new(
+        Point3::new(0.0, 6.0, 18.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vector3(0.0, 1.0, 0.0),
+        45.0,
+        16.0 / 9.0,
+        0.6,
+        6.0,
+    )
+    .with_aperture(Aperture::Polygon {
+        blades: 6,
+        rotation: 0.0,
+    })
+    .with_cat_eye(0.6);
+
+    Scene {
+        world: many_lights().world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The materials showcase shot through a 50mm double-Gauss lens at f/2, focused on the second row, with the
+/// showcase's spheres 20cm across.
+pub fn lens() -> Scene {
+    let camera = RealisticCamera::new(
+        Point3::new(0.0, 10.0, 16.0),
+        Point3::new(0.0, 1.0, -2.4),
+        Vector3(0.0, 1.0, 0.0),
+        LensSystem::double_gauss(),
+        43.3,
+        16.0 / 9.0,
+        0.01,
+    );
+
+    Scene {
+        world: materials_showcase().world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The materials showcase under an overcast sky of 8000 candelas per square metre, exposed as a photographer would
+/// at f/11 for 1/60 of a second at ISO 100 with a 26mm lens, focused on whatever is in the middle of the picture.
+/// The showcase's lamp is far too dim to show up against daylight.
+pub fn overcast() -> Scene {
+    let mut world = materials_showcase().world;
+    world.background = Background::Solid(Color::new(0.9, 0.95, 1.0) * 8000.0);
+
+    let camera = PerspectiveCamera::builder()
+        .look_from(Point3::new(0.0, 8.0, 9.0))
+        .look_at(Point3::new(0.0, 0.5, -3.0))
+        .field_of_view(FieldOfView::FocalLength {
+            focal_length: 26.0,
+            sensor_height: 24.0,
+        })
+        .build()
+        .autofocus(&world, 0.5, 0.5)
+        .with_exposure(Exposure::new(100.0, 1.0 / 60.0, 11.0), 0.01);
+
+    Scene {
+        world,
+        camera: Arc::new(camera),
+    }
+}
+
+/// The final scene of book one from above, through a wide-open lens tilted so that the plane in focus lies along the
+/// ground. The small spheres are sharp from front to back, while the tops of the big ones blur.
+pub fn tilt_shift() -> Scene {
+    let lookfrom = Point3::new(13.0, 5.0, 3.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let looking_down = ((lookfrom.y() - lookat.y()) / (lookfrom - lookat).length())
+        .asin()
+        .to_degrees();
+
+    let camera = PerspectiveCamera::builder()
+        .look_from(lookfrom)
+        .look_at(lookat)
+        .field_of_view(FieldOfView::Vertical(30.0))
+        .aperture(1.0)
+        .tilt(90.0 - looking_down, 0.0)
+        .build();
+
+    Scene {
+        world: book1_final().world,
+        camera: Arc::new(camera),
+    }
+}
@@ -0,0 +1,461 @@
+//! Wavelength-dependent optics, light spectra, and the conversions between spectral samples and RGB.
+//!
+//! Wavelengths are in nanometres throughout.
+
+use std::{ops::Mul, sync::OnceLock};
+
+use crate::math::{Color, Vector3};
+
+/// The range of wavelengths that are sampled, covering everything the eye is meaningfully sensitive to.
+pub const LAMBDA_MIN: f64 = 360.0;
+pub const LAMBDA_MAX: f64 = 830.0;
+
+/// The wavelength rays carry until the integrator picks one, roughly where the eye is most sensitive.
+pub const DEFAULT_WAVELENGTH: f64 = 550.0;
+
+/// Maps a uniform random number in `0..1` to a wavelength, favouring the ones the eye is most sensitive to
+/// (the distribution from Radziszewski et al. 2009, as used by pbrt).
+pub fn sample_wavelength(u: f64) -> f64 {
+    538.0 - 138.888_889 * (0.856_910_62 - 1.827_501_97 * u).atanh()
+}
+
+/// How many wavelengths each path carries in the spectral integrator: the hero wavelength and its companions.
+pub const HERO_SAMPLES: usize = 4;
+
+/// Maps a uniform random number in `0..1` to a hero wavelength, followed by companions spread evenly through the
+/// rest of the spectrum so that one path lights up several colours at once (Wilkie et al. 2014).
+///
+/// Each of them on its own is distributed like [`sample_wavelength`].
+pub fn sample_hero_wavelengths(u: f64) -> [f64; HERO_SAMPLES] {
+    let mut wavelengths = [0.0; HERO_SAMPLES];
+
+    for (i, wavelength) in wavelengths.iter_mut().enumerate() {
+        *wavelength = sample_wavelength((u + i as f64 / HERO_SAMPLES as f64).fract());
+    }
+
+    wavelengths
+}
+
+/// The probability density of [`sample_wavelength`] returning `lambda`.
+pub fn wavelength_pdf(lambda: f64) -> f64 {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+
+    0.003_939_804_2 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+/// A piecewise Gaussian, with a different width either side of the peak.
+fn lobe(lambda: f64, mean: f64, below: f64, above: f64) -> f64 {
+    let sigma = if lambda < mean { below } else { above };
+    let x = (lambda - mean) / sigma;
+
+    (-0.5 * x * x).exp()
+}
+
+/// The CIE 1931 colour matching functions at `lambda`, using the multi-lobe fit of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f64) -> Vector3 {
+    Vector3::new(
+        1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(lambda, 501.1, 20.4, 26.2),
+        0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1),
+        1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+/// Converts CIE XYZ to linear sRGB (D65 white point).
+pub fn xyz_to_rgb(xyz: Vector3) -> Color {
+    let Vector3(x, y, z) = xyz;
+
+    Color::new(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    )
+}
+
+/// The RGB weight of a sample at `lambda` drawn by [`sample_wavelength`].
+///
+/// It is scaled so that averaging it over many sampled wavelengths gives white, meaning a path that collapses to
+/// a single wavelength keeps the same brightness and (on average) the same colour.
+pub fn wavelength_to_rgb(lambda: f64) -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+
+    // The RGB colour of an equal-energy spectrum, integrated with the midpoint rule
+    let white = WHITE.get_or_init(|| {
+        let steps = 2000;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+
+        (0..steps)
+            .map(|i| xyz_to_rgb(cie_xyz(LAMBDA_MIN + (i as f64 + 0.5) * step)) * step)
+            .fold(Color::new(0.0, 0.0, 0.0), |a, b| a + b)
+    });
+
+    xyz_to_rgb(cie_xyz(lambda)) / (*white * wavelength_pdf(lambda))
+}
+
+/// Smits' (1999) basis spectra for turning RGB into a smooth reflectance spectrum, in 10 bins over 380–720 nm.
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// The value at `lambda` of a smooth reflectance spectrum with the colour `rgb` (Smits 1999).
+pub fn rgb_to_spectrum(rgb: Color, lambda: f64) -> f64 {
+    let bin = (((lambda - 380.0) / 34.0).floor().max(0.0) as usize).min(9);
+    let Vector3(r, g, b) = rgb;
+
+    // White up to the smallest channel, then the two secondary/primary colours that make up the rest
+    let (white, secondary, primary, small, middle, large) = if r <= g && r <= b {
+        if g <= b {
+            (r, SMITS_CYAN, SMITS_BLUE, r, g, b)
+        } else {
+            (r, SMITS_CYAN, SMITS_GREEN, r, b, g)
+        }
+    } else if g <= r && g <= b {
+        if r <= b {
+            (g, SMITS_MAGENTA, SMITS_BLUE, g, r, b)
+        } else {
+            (g, SMITS_MAGENTA, SMITS_RED, g, b, r)
+        }
+    } else if r <= g {
+        (b, SMITS_YELLOW, SMITS_GREEN, b, r, g)
+    } else {
+        (b, SMITS_YELLOW, SMITS_RED, b, g, r)
+    };
+
+    white * SMITS_WHITE[bin] + (middle - small) * secondary[bin] + (large - middle) * primary[bin]
+}
+
+/// The value at `lambda` of a light with the colour `rgb`, treating `(1, 1, 1)` as the sRGB white point, D65.
+pub fn rgb_to_emission(rgb: Color, lambda: f64) -> f64 {
+    static D65: OnceLock<Spectrum> = OnceLock::new();
+
+    rgb_to_spectrum(rgb, lambda)
+        * D65
+            .get_or_init(|| Spectrum::illuminant(Illuminant::D65))
+            .at(lambda)
+}
+
+/// Converts CIE XYZ to linear sRGB, scaled so that D65 light of luminance 1 comes out as exactly `(1, 1, 1)`.
+///
+/// This hides the small errors of the colour matching function fit, so grey stays grey.
+pub fn xyz_to_balanced_rgb(xyz: Vector3) -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+
+    let white = WHITE.get_or_init(|| {
+        let d65 = Spectrum::illuminant(Illuminant::D65);
+
+        xyz_to_rgb(d65.xyz())
+    });
+
+    xyz_to_rgb(xyz) / *white
+}
+
+/// Planck's law: the power a black body at `kelvin` radiates at `lambda`, up to a constant factor.
+fn planck(lambda: f64, kelvin: f64) -> f64 {
+    let metres = lambda * 1e-9;
+
+    // Second radiation constant, hc/k, in metre kelvins
+    let c2 = 1.438_777e-2;
+
+    1.0 / (metres.powi(5) * ((c2 / (metres * kelvin)).exp() - 1.0))
+}
+
+/// CIE standard illuminants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Illuminant {
+    /// Average midday daylight, the white point of sRGB.
+    D65,
+    /// Incandescent tungsten light.
+    A,
+    /// Cool white fluorescent.
+    F2,
+    /// Narrow-band (triphosphor) white fluorescent.
+    F11,
+}
+
+/// CIE D65 relative spectral power, every 10 nm from 360 to 830 nm.
+const D65: [f64; 48] = [
+    46.6383, 52.0891, 49.9755, 54.6482, 82.7549, 91.486, 93.4318, 86.6823, 104.865, 117.008,
+    117.812, 114.861, 115.923, 108.811, 109.354, 107.802, 104.79, 107.689, 104.405, 104.046, 100.0,
+    96.3342, 95.788, 88.6856, 90.0062, 89.5991, 87.6987, 83.2886, 83.6992, 80.0268, 80.2146,
+    82.2778, 78.2842, 69.7213, 71.6091, 74.349, 61.604, 69.8856, 75.087, 63.5927, 46.4182, 66.8054,
+    63.3828, 64.304, 59.4519, 51.959, 57.4406, 60.3125,
+];
+
+/// CIE F2 relative spectral power, every 5 nm from 380 to 780 nm.
+const F2: [f64; 81] = [
+    1.18, 1.48, 1.84, 2.15, 3.44, 15.69, 3.85, 3.74, 4.19, 4.62, 5.06, 34.98, 11.81, 6.27, 6.63,
+    6.93, 7.19, 7.40, 7.54, 7.62, 7.65, 7.62, 7.62, 7.45, 7.28, 7.15, 7.05, 7.04, 7.16, 7.47, 8.04,
+    8.88, 10.01, 24.88, 16.64, 14.59, 16.16, 17.56, 18.62, 21.47, 22.79, 19.29, 18.66, 17.73,
+    16.54, 15.21, 13.80, 12.36, 10.95, 9.65, 8.40, 7.32, 6.31, 5.43, 4.68, 4.02, 3.45, 2.96, 2.55,
+    2.19, 1.89, 1.64, 1.53, 1.27, 1.10, 0.99, 0.88, 0.76, 0.68, 0.61, 0.56, 0.54, 0.51, 0.47, 0.47,
+    0.43, 0.46, 0.47, 0.40, 0.33, 0.27,
+];
+
+/// CIE F11 relative spectral power, every 5 nm from 380 to 780 nm.
+const F11: [f64; 81] = [
+    0.91, 0.63, 0.46, 0.37, 1.29, 12.68, 1.59, 1.79, 2.46, 3.33, 4.49, 33.94, 12.13, 6.95, 7.19,
+    7.12, 6.72, 6.13, 5.46, 4.79, 5.66, 14.29, 14.96, 8.97, 4.72, 2.33, 1.47, 1.10, 0.89, 0.83,
+    1.18, 4.90, 39.59, 72.84, 32.61, 7.52, 2.83, 1.96, 1.67, 4.43, 11.28, 14.76, 12.73, 9.74, 7.33,
+    9.72, 55.27, 42.58, 13.18, 13.16, 12.26, 5.11, 2.07, 2.34, 3.58, 3.01, 2.48, 2.14, 1.54, 1.33,
+    1.46, 1.94, 2.00, 1.20, 1.35, 4.10, 5.58, 2.51, 0.57, 0.27, 0.23, 0.21, 0.24, 0.24, 0.20, 0.24,
+    0.32, 0.26, 0.16, 0.12, 0.09,
+];
+
+/// Linearly interpolates a table of values spaced `step` nm apart starting at `start`, and zero outside it.
+fn interpolate(table: &[f64], start: f64, step: f64, lambda: f64) -> f64 {
+    let x = (lambda - start) / step;
+    if x < 0.0 || x > (table.len() - 1) as f64 {
+        return 0.0;
+    }
+
+    let i = (x as usize).min(table.len() - 2);
+    let t = x - i as f64;
+
+    table[i] * (1.0 - t) + table[i + 1] * t
+}
+
+/// The spectrum of a light source. The constructors scale it to a luminance of 1; multiply it to brighten it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    /// Values every `SPECTRUM_STEP` nm, starting at `LAMBDA_MIN`.
+    samples: Vec<f64>,
+}
+
+const SPECTRUM_STEP: f64 = 5.0;
+
+impl Mul<f64> for Spectrum {
+    type Output = Spectrum;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Spectrum {
+            samples: self.samples.iter().map(|value| value * rhs).collect(),
+        }
+    }
+}
+
+impl Spectrum {
+    fn from_fn(f: impl Fn(f64) -> f64) -> Self {
+        let count = ((LAMBDA_MAX - LAMBDA_MIN) / SPECTRUM_STEP) as usize + 1;
+        let mut spectrum = Self {
+            samples: (0..count)
+                .map(|i| f(LAMBDA_MIN + i as f64 * SPECTRUM_STEP))
+                .collect(),
+        };
+
+        let luminance = spectrum.xyz().y();
+        for sample in &mut spectrum.samples {
+            *sample /= luminance;
+        }
+
+        spectrum
+    }
+
+    /// The light given off by an ideal black body at a colour temperature of `kelvin`.
+    pub fn blackbody(kelvin: f64) -> Self {
+        Self::from_fn(|lambda| planck(lambda, kelvin))
+    }
+
+    pub fn illuminant(illuminant: Illuminant) -> Self {
+        match illuminant {
+            Illuminant::D65 => Self::from_fn(|lambda| interpolate(&D65, 360.0, 10.0, lambda)),
+            Illuminant::A => Self::blackbody(2856.0),
+            Illuminant::F2 => Self::from_fn(|lambda| interpolate(&F2, 380.0, 5.0, lambda)),
+            Illuminant::F11 => Self::from_fn(|lambda| interpolate(&F11, 380.0, 5.0, lambda)),
+        }
+    }
+
+    pub fn at(&self, lambda: f64) -> f64 {
+        interpolate(&self.samples, LAMBDA_MIN, SPECTRUM_STEP, lambda)
+    }
+
+    /// The CIE XYZ colour of the spectrum, integrated with the trapezoidal rule.
+    pub fn xyz(&self) -> Vector3 {
+        let last = self.samples.len() - 1;
+
+        self.samples
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let weight = if i == 0 || i == last { 0.5 } else { 1.0 };
+
+                cie_xyz(LAMBDA_MIN + i as f64 * SPECTRUM_STEP) * (value * weight * SPECTRUM_STEP)
+            })
+            .fold(Vector3::new(0.0, 0.0, 0.0), |a, b| a + b)
+    }
+
+    /// The RGB colour that the RGB integrator uses in place of this spectrum.
+    pub fn to_rgb(&self) -> Color {
+        xyz_to_balanced_rgb(self.xyz())
+    }
+}
+
+/// An index of refraction, which can vary with wavelength to split white light into a rainbow (dispersion).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ior {
+    Constant(f64),
+    /// Cauchy's equation `a + b / λ²`, with `λ` in micrometres.
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// The Sellmeier equation `n² = 1 + Σ bᵢλ² / (λ² - cᵢ)`, with `λ` in micrometres.
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl From<f64> for Ior {
+    fn from(value: f64) -> Self {
+        Ior::Constant(value)
+    }
+}
+
+impl Ior {
+    /// Schott N-BK7, the most common optical crown glass.
+    pub fn bk7() -> Self {
+        Ior::Sellmeier {
+            b: [1.039_612_12, 0.231_792_344, 1.010_469_45],
+            c: [0.006_000_698_67, 0.020_017_914_4, 103.560_653],
+        }
+    }
+
+    /// Schott SF11, a dense flint glass with strong dispersion.
+    pub fn flint() -> Self {
+        Ior::Sellmeier {
+            b: [1.737_596_95, 0.313_747_346, 1.898_781_01],
+            c: [0.013_188_707, 0.062_306_814_2, 155.236_29],
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Ior::Sellmeier {
+            b: [0.3306, 4.3356, 0.0],
+            c: [0.1750 * 0.1750, 0.1060 * 0.1060, 0.0],
+        }
+    }
+
+    /// Fits Cauchy's equation to the index `nd` at the yellow helium d line (587.6 nm) and the Abbe number `vd`, the
+    /// two figures glass catalogues and lens prescriptions give. Lower Abbe numbers disperse more.
+    pub fn from_abbe(nd: f64, vd: f64) -> Self {
+        let (f, c, d) = (0.4861, 0.6563, 0.5876);
+        let b = (nd - 1.0) / vd / (1.0 / (f * f) - 1.0 / (c * c));
+
+        Ior::Cauchy {
+            a: nd - b / (d * d),
+            b,
+        }
+    }
+
+    pub fn at(&self, lambda: f64) -> f64 {
+        let micrometres = lambda / 1000.0;
+        let l2 = micrometres * micrometres;
+
+        match *self {
+            Ior::Constant(n) => n,
+            Ior::Cauchy { a, b } => a + b / l2,
+            Ior::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        math::{Color, Vector3},
+        spectrum::{
+            rgb_to_spectrum, sample_wavelength, wavelength_to_rgb, Illuminant, Ior, Spectrum,
+        },
+    };
+
+    fn chromaticity(xyz: Vector3) -> (f64, f64) {
+        let sum = xyz.x() + xyz.y() + xyz.z();
+
+        (xyz.x() / sum, xyz.y() / sum)
+    }
+
+    #[test]
+    fn wavelength_weights_average_to_white() {
+        let steps = 20000;
+        let sum = (0..steps)
+            .map(|i| wavelength_to_rgb(sample_wavelength((i as f64 + 0.5) / steps as f64)))
+            .fold(Color::new(0.0, 0.0, 0.0), |a, b| a + b)
+            / steps as f64;
+
+        for channel in [sum.x(), sum.y(), sum.z()] {
+            assert!((channel - 1.0).abs() < 1e-2, "{sum:?}");
+        }
+    }
+
+    #[test]
+    fn sellmeier_presets_match_reference_indices() {
+        // Refractive indices at the sodium D line (589.3 nm)
+        assert!((Ior::bk7().at(589.3) - 1.5168).abs() < 1e-3);
+        assert!((Ior::flint().at(589.3) - 1.7847).abs() < 1e-3);
+        assert!((Ior::diamond().at(589.3) - 2.417).abs() < 5e-3);
+
+        // Blue light bends more than red
+        assert!(Ior::bk7().at(450.0) > Ior::bk7().at(650.0));
+
+        // Fitting BK7's catalogue figures follows its Sellmeier curve across the visible range
+        let fit = Ior::from_abbe(1.5168, 64.17);
+        for lambda in [450.0, 486.1, 587.6, 656.3] {
+            assert!((fit.at(lambda) - Ior::bk7().at(lambda)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn illuminants_have_reference_white_points() {
+        let references = [
+            (Illuminant::D65, (0.3127, 0.3290)),
+            (Illuminant::A, (0.4476, 0.4074)),
+            (Illuminant::F2, (0.3721, 0.3751)),
+            (Illuminant::F11, (0.3805, 0.3769)),
+        ];
+
+        for (illuminant, (x, y)) in references {
+            let (cx, cy) = chromaticity(Spectrum::illuminant(illuminant).xyz());
+
+            assert!(
+                (cx - x).abs() < 5e-3 && (cy - y).abs() < 5e-3,
+                "{illuminant:?}: {cx}, {cy}"
+            );
+        }
+    }
+
+    #[test]
+    fn upsampled_colours_keep_their_dominant_band() {
+        assert!((rgb_to_spectrum(Color::new(0.5, 0.5, 0.5), 550.0) - 0.5).abs() < 1e-3);
+
+        let red = Color::new(0.9, 0.1, 0.1);
+        assert!(rgb_to_spectrum(red, 650.0) > 0.8 && rgb_to_spectrum(red, 450.0) < 0.2);
+    }
+}
@@ -0,0 +1,205 @@
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+
+use crate::math::{Color, Point3, Vector3};
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color;
+}
+
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Box<Self> {
+        Box::new(Self { color })
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: Point3) -> Color {
+        self.color
+    }
+}
+
+/// A 3D checkerboard, alternating between two textures every `scale` units.
+pub struct CheckerTexture {
+    pub scale: f64,
+    pub even: Box<dyn Texture>,
+    pub odd: Box<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Box<dyn Texture>, odd: Box<dyn Texture>) -> Box<Self> {
+        Box::new(Self { scale, even, odd })
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Box<Self> {
+        Self::new(scale, SolidColor::new(even), SolidColor::new(odd))
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: Point3) -> Color {
+        let x = (point.x() / self.scale).floor() as i64;
+        let y = (point.y() / self.scale).floor() as i64;
+        let z = (point.z() / self.scale).floor() as i64;
+
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise with a fixed seed, so every render thread builds an identical world.
+pub struct Perlin {
+    gradients: Vec<Vector3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Perlin {
+    pub fn new() -> Self {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .unit_vector()
+            })
+            .collect();
+
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+
+        Self {
+            gradients,
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+        }
+    }
+
+    pub fn noise(&self, point: Point3) -> f64 {
+        let u = point.x() - point.x().floor();
+        let v = point.y() - point.y().floor();
+        let w = point.z() - point.z().floor();
+
+        let i = point.x().floor() as i64;
+        let j = point.y().floor() as i64;
+        let k = point.z().floor() as i64;
+
+        // Hermite smoothing of the interpolation weights
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accum = 0.0;
+
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize]];
+
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vector3::new(u - fi, v - fj, w - fk);
+
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * gradient.dot(&weight);
+                }
+            }
+        }
+
+        accum
+    }
+
+    /// Sums several octaves of noise for a more natural, cloudy look.
+    pub fn turbulence(&self, point: Point3, depth: usize) -> f64 {
+        let mut accum = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(point);
+            weight *= 0.5;
+            point *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+/// A marble-like texture made of sine bands perturbed by Perlin turbulence.
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f64,
+}
+
+impl NoiseTexture {
+    pub fn new(scale: f64) -> Box<Self> {
+        Box::new(Self {
+            noise: Perlin::new(),
+            scale,
+        })
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: Point3) -> Color {
+        let phase = self.scale * point.z() + 10.0 * self.noise.turbulence(point, 7);
+
+        Color::new(0.5, 0.5, 0.5) * (1.0 + phase.sin())
+    }
+}
+
+/// Maps texture coordinates onto a `u` by `v` checkerboard, useful for checking UV parameterizations.
+pub struct UvChecker {
+    pub divisions: u32,
+    pub even: Color,
+    pub odd: Color,
+}
+
+impl UvChecker {
+    pub fn new(divisions: u32, even: Color, odd: Color) -> Box<Self> {
+        Box::new(Self {
+            divisions,
+            even,
+            odd,
+        })
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: f64, v: f64, _point: Point3) -> Color {
+        let i = (u * self.divisions as f64).floor() as i64;
+        let j = (v * self.divisions as f64).floor() as i64;
+
+        if (i + j) % 2 == 0 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Interference in a thin transparent film on top of a surface, which gives soap bubbles, oil slicks and coated
+//! lenses their colours.
+//!
+//! Light reflected off the top of the film and light reflected off the surface beneath interfere, reinforcing some
+//! wavelengths and cancelling others depending on the film's thickness and the viewing angle.
+
+use std::{
+    ops::{Add, Div, Mul, Sub},
+    sync::OnceLock,
+};
+
+use crate::{
+    math::{Color, Ray, Vector3},
+    spectrum::{cie_xyz, xyz_to_balanced_rgb, Illuminant, Spectrum},
+};
+
+/// Just enough complex arithmetic for the Fresnel equations of absorbing materials.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn norm_squared(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn sqrt(self) -> Self {
+        let length = self.norm_squared().sqrt();
+        let re = ((length + self.re) / 2.0).max(0.0).sqrt();
+        let im = ((length - self.re) / 2.0).max(0.0).sqrt();
+
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    /// `e^(iθ)`
+    fn from_phase(theta: f64) -> Self {
+        Self::new(theta.cos(), theta.sin())
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let denominator = rhs.norm_squared();
+
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denominator,
+            (self.im * rhs.re - self.re * rhs.im) / denominator,
+        )
+    }
+}
+
+/// A thin transparent layer coating a surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThinFilm {
+    /// In nanometres; visible colours appear from roughly 100 to 1000 nm.
+    pub thickness: f64,
+    pub refraction_index: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, refraction_index: f64) -> Self {
+        Self {
+            thickness,
+            refraction_index,
+        }
+    }
+
+    /// The fraction of light at `lambda` reflected by the film on top of a substrate with complex index of
+    /// refraction `substrate`, for light arriving through a medium of index `outside` at an angle with cosine
+    /// `cos_theta`. Averages both polarizations.
+    fn reflectance_complex(
+        &self,
+        cos_theta: f64,
+        outside: f64,
+        substrate: Complex,
+        lambda: f64,
+    ) -> f64 {
+        let cos_theta = cos_theta.clamp(0.0, 1.0);
+        let sin2 = 1.0 - cos_theta * cos_theta;
+
+        // Snell's law, with complex cosines so that total internal reflection and absorption just work
+        let cosine_in = |n: Complex| {
+            let ratio = Complex::from(outside) / n;
+
+            (Complex::from(1.0) - ratio * ratio * Complex::from(sin2)).sqrt()
+        };
+
+        let n1 = Complex::from(outside);
+        let n2 = Complex::from(self.refraction_index);
+        let n3 = substrate;
+        let (cos1, cos2, cos3) = (Complex::from(cos_theta), cosine_in(n2), cosine_in(n3));
+
+        // The phase picked up by one round trip through the film, which turns into decay if the light can't
+        // propagate in it
+        let phase = Complex::from(4.0 * std::f64::consts::PI * self.thickness / lambda) * n2 * cos2;
+        let shift = Complex::from_phase(phase.re) * Complex::from((-phase.im.abs()).exp());
+
+        let airy = |r12: Complex, r23: Complex| {
+            ((r12 + r23 * shift) / (Complex::from(1.0) + r12 * r23 * shift)).norm_squared()
+        };
+
+        let s = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+            (na * ca - nb * cb) / (na * ca + nb * cb)
+        };
+        let p = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+            (nb * ca - na * cb) / (nb * ca + na * cb)
+        };
+
+        let reflectance_s = airy(s(n1, cos1, n2, cos2), s(n2, cos2, n3, cos3));
+        let reflectance_p = airy(p(n1, cos1, n2, cos2), p(n2, cos2, n3, cos3));
+
+        ((reflectance_s + reflectance_p) / 2.0).clamp(0.0, 1.0)
+    }
+
+    /// Reflectance at `lambda` of the film between two dielectrics, for light arriving through the one with index
+    /// of refraction `outside`.
+    pub fn reflectance_dielectric(
+        &self,
+        cos_theta: f64,
+        outside: f64,
+        substrate: f64,
+        lambda: f64,
+    ) -> f64 {
+        self.reflectance_complex(cos_theta, outside, Complex::from(substrate), lambda)
+    }
+
+    /// Reflectance at `lambda` of the film on a conductor with complex index of refraction `eta + i k`, seen from
+    /// air.
+    pub fn reflectance_conductor(&self, cos_theta: f64, eta: f64, k: f64, lambda: f64) -> f64 {
+        self.reflectance_complex(cos_theta, 1.0, Complex::new(eta, k), lambda)
+    }
+}
+
+/// Wavelengths every 20 nm across the visible range, with the RGB weight of each so that a spectrum of 1 under
+/// daylight gives `(1, 1, 1)`.
+fn quadrature() -> &'static [(f64, Color)] {
+    static QUADRATURE: OnceLock<Vec<(f64, Color)>> = OnceLock::new();
+
+    QUADRATURE.get_or_init(|| {
+        let daylight = Spectrum::illuminant(Illuminant::D65);
+        let points: Vec<(f64, Color)> = (0..21)
+            .map(|i| {
+                let lambda = 380.0 + 20.0 * i as f64;
+
+                (
+                    lambda,
+                    xyz_to_balanced_rgb(cie_xyz(lambda) * daylight.at(lambda)),
+                )
+            })
+            .collect();
+
+        let total = points
+            .iter()
+            .fold(Color::new(0.0, 0.0, 0.0), |sum, (_, weight)| sum + *weight);
+
+        points
+            .into_iter()
+            .map(|(lambda, weight)| (lambda, weight / total))
+            .collect()
+    })
+}
+
+/// Turns a reflectance spectrum into RGB by integrating it over the visible range.
+pub fn spectrum_to_rgb(reflectance: impl Fn(f64) -> f64) -> Color {
+    quadrature()
+        .iter()
+        .fold(Color::new(0.0, 0.0, 0.0), |sum, &(lambda, weight)| {
+            sum + weight * reflectance(lambda)
+        })
+}
+
+/// The reflectance of a film as seen by `ray`: exact at its wavelength once the path only carries one, otherwise
+/// integrated into RGB.
+pub fn reflectance_for_ray(ray: Ray, reflectance: impl Fn(f64) -> f64) -> Color {
+    if ray.dispersed {
+        let value = reflectance(ray.wavelength);
+
+        Color::new(value, value, value)
+    } else {
+        spectrum_to_rgb(reflectance)
+    }
+}
+
+/// Estimates a material constant at `lambda` from its values for the red, green and blue channels, treating those
+/// as samples at 650, 550 and 450 nm.
+pub fn rgb_at_wavelength(rgb: Vector3, lambda: f64) -> f64 {
+    if lambda <= 450.0 {
+        rgb.z()
+    } else if lambda <= 550.0 {
+        let t = (lambda - 450.0) / 100.0;
+
+        rgb.z() + t * (rgb.y() - rgb.z())
+    } else if lambda <= 650.0 {
+        let t = (lambda - 550.0) / 100.0;
+
+        rgb.y() + t * (rgb.x() - rgb.y())
+    } else {
+        rgb.x()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{thin_film::ThinFilm, utils::fresnel_dielectric};
+
+    #[test]
+    fn vanishing_film_matches_plain_fresnel() {
+        let film = ThinFilm::new(0.0, 1.33);
+
+        for cos_theta in [1.0, 0.7, 0.2] {
+            let expected = fresnel_dielectric(cos_theta, 1.5);
+            let reflectance = film.reflectance_dielectric(cos_theta, 1.0, 1.5, 550.0);
+
+            assert!(
+                (reflectance - expected).abs() < 1e-9,
+                "{reflectance} {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn quarter_wave_coating_cancels_reflection() {
+        // An anti-reflective coating: index sqrt(n), a quarter of a wavelength thick inside the film
+        let index = 1.5f64.sqrt();
+        let film = ThinFilm::new(550.0 / (4.0 * index), index);
+
+        assert!(film.reflectance_dielectric(1.0, 1.0, 1.5, 550.0) < 1e-6);
+        assert!(film.reflectance_dielectric(1.0, 1.0, 1.5, 420.0) > 1e-3);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::math::{Point3, Vector3};
+
+type Matrix3 = [[f64; 3]; 3];
+
+const IDENTITY: Matrix3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+fn mul_vector(m: &Matrix3, v: Vector3) -> Vector3 {
+    Vector3(
+        m[0][0] * v.0 + m[0][1] * v.1 + m[0][2] * v.2,
+        m[1][0] * v.0 + m[1][1] * v.1 + m[1][2] * v.2,
+        m[2][0] * v.0 + m[2][1] * v.1 + m[2][2] * v.2,
+    )
+}
+
+fn mul_matrix(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let mut m = [[0.0; 3]; 3];
+
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+
+    m
+}
+
+fn transpose(m: &Matrix3) -> Matrix3 {
+    let mut t = [[0.0; 3]; 3];
+
+    for (i, row) in m.iter().enumerate() {
+        for (j, value) in row.iter().enumerate() {
+            t[j][i] = *value;
+        }
+    }
+
+    t
+}
+
+/// An affine transform (a linear part followed by a translation), stored together with its inverse.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    linear: Matrix3,
+    inverse: Matrix3,
+    translation: Vector3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            linear: IDENTITY,
+            inverse: IDENTITY,
+            translation: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn translation(offset: Vector3) -> Self {
+        Self {
+            translation: offset,
+            ..Self::identity()
+        }
+    }
+
+    /// A rotation of `degrees` counter-clockwise about `axis` (Rodrigues' formula).
+    pub fn rotation(axis: Vector3, degrees: f64) -> Self {
+        let Vector3(x, y, z) = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = 1.0 - cos;
+
+        let linear = [
+            [cos + x * x * k, x * y * k - z * sin, x * z * k + y * sin],
+            [y * x * k + z * sin, cos + y * y * k, y * z * k - x * sin],
+            [z * x * k - y * sin, z * y * k + x * sin, cos + z * z * k],
+        ];
+
+        Self {
+            linear,
+            inverse: transpose(&linear),
+            translation: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn rotation_y(degrees: f64) -> Self {
+        Self::rotation(Vector3::new(0.0, 1.0, 0.0), degrees)
+    }
+
+    pub fn scale(factors: Vector3) -> Self {
+        Self {
+            linear: [
+                [factors.x(), 0.0, 0.0],
+                [0.0, factors.y(), 0.0],
+                [0.0, 0.0, factors.z()],
+            ],
+            inverse: [
+                [1.0 / factors.x(), 0.0, 0.0],
+                [0.0, 1.0 / factors.y(), 0.0],
+                [0.0, 0.0, 1.0 / factors.z()],
+            ],
+            translation: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn uniform_scale(factor: f64) -> Self {
+        Self::scale(Vector3::new(factor, factor, factor))
+    }
+
+    /// Returns the transform that applies `self` first and then `next`.
+    pub fn then(&self, next: &Transform) -> Self {
+        Self {
+            linear: mul_matrix(&next.linear, &self.linear),
+            inverse: mul_matrix(&self.inverse, &next.inverse),
+            translation: mul_vector(&next.linear, self.translation) + next.translation,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            linear: self.inverse,
+            inverse: self.linear,
+            translation: -mul_vector(&self.inverse, self.translation),
+        }
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        mul_vector(&self.linear, p) + self.translation
+    }
+
+    pub fn vector(&self, v: Vector3) -> Vector3 {
+        mul_vector(&self.linear, v)
+    }
+
+    /// Transforms a surface normal, which needs the inverse transpose to stay perpendicular.
+    pub fn normal(&self, n: Vector3) -> Vector3 {
+        mul_vector(&transpose(&self.inverse), n)
+    }
+}
+
+/// A unit quaternion representing a rotation, which (unlike a matrix) can be smoothly interpolated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub v: Vector3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            v: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn from_axis_angle(axis: Vector3, degrees: f64) -> Self {
+        let (sin, cos) = (degrees.to_radians() / 2.0).sin_cos();
+
+        Self {
+            w: cos,
+            v: axis.unit_vector() * sin,
+        }
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.v.dot(&other.v)
+    }
+
+    fn normalized(&self) -> Self {
+        let length = self.dot(self).sqrt();
+
+        Self {
+            w: self.w / length,
+            v: self.v / length,
+        }
+    }
+
+    /// Spherical linear interpolation, taking the shorter way around.
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let mut cos_theta = self.dot(other);
+        let mut other = *other;
+
+        if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            other = Self {
+                w: -other.w,
+                v: -other.v,
+            };
+        }
+
+        // Nearly parallel: fall back to a normalized lerp to avoid dividing by ~0
+        if cos_theta > 0.9995 {
+            return Self {
+                w: self.w + t * (other.w - self.w),
+                v: self.v + t * (other.v - self.v),
+            }
+            .normalized();
+        }
+
+        let theta = cos_theta.acos();
+        let a = ((1.0 - t) * theta).sin() / theta.sin();
+        let b = (t * theta).sin() / theta.sin();
+
+        Self {
+            w: a * self.w + b * other.w,
+            v: a * self.v + b * other.v,
+        }
+    }
+
+    fn to_matrix(self) -> Matrix3 {
+        let Quaternion {
+            w,
+            v: Vector3(x, y, z),
+        } = self;
+
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+}
+
+/// A transform split into scale, then rotation, then translation, so each part can be interpolated on its own.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyframe {
+    pub scale: Vector3,
+    pub rotation: Quaternion,
+    pub translation: Vector3,
+}
+
+impl Default for Keyframe {
+    fn default() -> Self {
+        Self {
+            scale: Vector3::new(1.0, 1.0, 1.0),
+            rotation: Quaternion::identity(),
+            translation: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Keyframe {
+    pub fn to_transform(&self) -> Transform {
+        let rotation = self.rotation.to_matrix();
+
+        Transform::scale(self.scale)
+            .then(&Transform {
+                linear: rotation,
+                inverse: transpose(&rotation),
+                translation: Vector3::new(0.0, 0.0, 0.0),
+            })
+            .then(&Transform::translation(self.translation))
+    }
+}
+
+/// A transform that moves from `start` at `time0` to `end` at `time1`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnimatedTransform {
+    pub start: Keyframe,
+    pub end: Keyframe,
+    pub time0: f64,
+    pub time1: f64,
+}
+
+impl AnimatedTransform {
+    pub fn new(start: Keyframe, end: Keyframe, time0: f64, time1: f64) -> Self {
+        Self {
+            start,
+            end,
+            time0,
+            time1,
+        }
+    }
+
+    /// The transform at `time`, holding still outside of `time0..time1`.
+    pub fn at(&self, time: f64) -> Transform {
+        let t = if self.time1 > self.time0 {
+            ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Keyframe {
+            scale: self.start.scale + t * (self.end.scale - self.start.scale),
+            rotation: self.start.rotation.slerp(&self.end.rotation, t),
+            translation: self.start.translation
+                + t * (self.end.translation - self.start.translation),
+        }
+        .to_transform()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        math::Vector3,
+        transform::{AnimatedTransform, Keyframe, Quaternion, Transform},
+    };
+
+    fn assert_near(a: Vector3, b: Vector3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn rotation_y_quarter_turn() {
+        let t = Transform::rotation_y(90.0);
+
+        assert_near(t.point(Vector3(1.0, 0.0, 0.0)), Vector3(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn then_applies_in_order() {
+        let t = Transform::uniform_scale(2.0).then(&Transform::translation(Vector3(1.0, 0.0, 0.0)));
+
+        assert_near(t.point(Vector3(1.0, 1.0, 1.0)), Vector3(3.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        let t = Transform::scale(Vector3(1.0, 2.0, 3.0))
+            .then(&Transform::rotation(Vector3(1.0, 1.0, 0.0), 30.0))
+            .then(&Transform::translation(Vector3(4.0, -5.0, 6.0)));
+        let p = Vector3(0.3, -0.7, 2.0);
+
+        assert_near(t.inverse().point(t.point(p)), p);
+    }
+
+    #[test]
+    fn animated_transform_interpolates_rotation() {
+        let animated = AnimatedTransform::new(
+            Keyframe::default(),
+            Keyframe {
+                rotation: Quaternion::from_axis_angle(Vector3(0.0, 1.0, 0.0), 90.0),
+                ..Keyframe::default()
+            },
+            0.0,
+            1.0,
+        );
+
+        assert_near(
+            animated.at(0.5).point(Vector3(1.0, 0.0, 0.0)),
+            Transform::rotation_y(45.0).point(Vector3(1.0, 0.0, 0.0)),
+        );
+    }
+}
@@ -13,6 +13,15 @@ pub fn write_color(pixel_color: Color, samples_per_pixel: u32) -> String {
     format!("{ir} {ig} {ib}")
 }
 
+/// Perceived brightness of a linear RGB colour.
+pub fn luminance(color: Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
+
+pub fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
+
 pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     if x < min {
         min
@@ -43,6 +52,19 @@ pub fn random_unit_vector(rng: &mut ThreadRng) -> Vector3 {
     random_in_unit_sphere(rng).unit_vector()
 }
 
+/// A direction in the `+z` hemisphere, more likely the closer it is to `+z` (cosine-weighted).
+pub fn random_cosine_direction(rng: &mut ThreadRng) -> Vector3 {
+    let r1 = rng.gen::<f64>();
+    let r2 = rng.gen::<f64>();
+    let phi = 2.0 * std::f64::consts::PI * r1;
+
+    Vector3::new(
+        phi.cos() * r2.sqrt(),
+        phi.sin() * r2.sqrt(),
+        (1.0 - r2).sqrt(),
+    )
+}
+
 // Angle of reflectance = Angle of incidence
 pub fn reflect(v: Vector3, n: Vector3) -> Vector3 {
     v - 2.0 * v.dot(&n) * n
@@ -56,6 +78,62 @@ pub fn refract(uv: Vector3, n: Vector3, etai_over_etat: f64) -> Vector3 {
     r_out_perp + r_out_parallel
 }
 
+/// Schlick's approximation of Fresnel reflectance, given the reflectance `f0` at normal incidence.
+pub fn fresnel_schlick(f0: Color, cos_theta: f64) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+
+    f0 + (Color::new(1.0, 1.0, 1.0) - f0) * weight
+}
+
+/// Exact Fresnel reflectance of an interface between two dielectrics, for unpolarized light arriving at an angle
+/// with cosine `cos_theta_i`, where `eta` is the ratio of the far side's index of refraction to the near side's.
+///
+/// Returns 1 past the critical angle, where all of the light is reflected (total internal reflection).
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Exact Fresnel reflectance of a conductor with complex index of refraction `eta + i k`, for unpolarized light
+/// arriving from air at an angle with cosine `cos_theta`.
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    let channel = |eta: f64, k: f64| {
+        let cos2 = cos_theta * cos_theta;
+        let sin2 = 1.0 - cos2;
+        let eta2 = eta * eta;
+        let k2 = k * k;
+
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        0.5 * (rs + rp)
+    };
+
+    Color::new(
+        channel(eta.x(), k.x()),
+        channel(eta.y(), k.y()),
+        channel(eta.z(), k.z()),
+    )
+}
+
 pub fn random_in_unit_disk(rng: &mut ThreadRng) -> Vector3 {
     loop {
         let p = Vector3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
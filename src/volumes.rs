@@ -0,0 +1,504 @@
+//! Heterogeneous participating media, like clouds and explosions, whose density varies from place to place.
+
+use std::io::{self, Read};
+
+use rand::{rngs::ThreadRng, thread_rng, Rng};
+
+use crate::{
+    aabb::Aabb,
+    hittables::{HitRecord, Hittable},
+    materials::{HenyeyGreenstein, Material},
+    math::{Color, Point3, Ray, Vector3},
+    textures::Perlin,
+};
+
+/// A dense 3D grid of density values, with optional emission, covering the unit cube.
+pub struct VoxelGrid {
+    pub resolution: [usize; 3],
+    density: Vec<f64>,
+    emission: Option<Vec<Color>>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_i32(reader: &mut dyn Read) -> io::Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+/// Multiplies sizes read from a file, failing rather than overflowing on absurd ones.
+fn checked_product(sizes: &[usize]) -> io::Result<usize> {
+    sizes
+        .iter()
+        .try_fold(1usize, |product, &size| product.checked_mul(size))
+        .ok_or_else(|| invalid_data("grid too large"))
+}
+
+/// The number of voxels in a grid whose resolution was read from a file, which must have at least one.
+fn voxel_count(resolution: [usize; 3]) -> io::Result<usize> {
+    if resolution.contains(&0) {
+        return Err(invalid_data("empty grid"));
+    }
+
+    checked_product(&resolution)
+}
+
+fn read_f32s(reader: &mut dyn Read, count: usize) -> io::Result<Vec<f64>> {
+    let length = checked_product(&[count, 4])?;
+
+    // Sizes come from the file's header, so only allocate as much as the file actually holds
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+        .collect())
+}
+
+impl VoxelGrid {
+    /// Creates a grid from voxel values stored with `x` varying fastest, then `y`, then `z`.
+    pub fn new(resolution: [usize; 3], density: Vec<f64>, emission: Option<Vec<Color>>) -> Self {
+        assert!(
+            resolution.iter().all(|&size| size > 0),
+            "grid has no voxels"
+        );
+        let count = resolution.iter().product();
+
+        assert_eq!(density.len(), count, "density doesn't match the resolution");
+        if let Some(emission) = &emission {
+            assert_eq!(
+                emission.len(),
+                count,
+                "emission doesn't match the resolution"
+            );
+        }
+
+        Self {
+            resolution,
+            density,
+            emission,
+        }
+    }
+
+    /// Fills the grid by calling `f` with the center of each voxel in unit cube coordinates, which returns that
+    /// voxel's density and emission.
+    pub fn from_fn<F>(resolution: [usize; 3], f: F) -> Self
+    where
+        F: Fn(Point3) -> (f64, Color),
+    {
+        let [nx, ny, nz] = resolution;
+        let mut density = Vec::with_capacity(nx * ny * nz);
+        let mut emission = Vec::with_capacity(nx * ny * nz);
+
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let point = Point3::new(
+                        (x as f64 + 0.5) / nx as f64,
+                        (y as f64 + 0.5) / ny as f64,
+                        (z as f64 + 0.5) / nz as f64,
+                    );
+                    let (d, e) = f(point);
+
+                    density.push(d.max(0.0));
+                    emission.push(e);
+                }
+            }
+        }
+
+        let emission = emission.iter().any(|e| !e.near_zero()).then_some(emission);
+
+        Self::new(resolution, density, emission)
+    }
+
+    /// Generates a billowing cloud: turbulent noise that fades out towards the edge of the unit cube.
+    pub fn cloud(resolution: usize, frequency: f64) -> Self {
+        let noise = Perlin::new();
+
+        Self::from_fn([resolution; 3], |point| {
+            let falloff = 1.0 - 2.0 * (point - Point3::new(0.5, 0.5, 0.5)).length();
+            let density = noise.turbulence(point * frequency, 5) + falloff - 0.5;
+
+            (density.max(0.0) * 2.0, Color::new(0.0, 0.0, 0.0))
+        })
+    }
+
+    /// Reads raw little-endian `f32` densities, as exported by many simulation tools.
+    pub fn read_raw(reader: &mut dyn Read, resolution: [usize; 3]) -> io::Result<Self> {
+        let density = read_f32s(reader, voxel_count(resolution)?)?;
+
+        Ok(Self::new(resolution, density, None))
+    }
+
+    /// Reads a Mitsuba `.vol` grid of `f32`s with one channel (density) or four (density and RGB emission).
+    pub fn read_vol(reader: &mut dyn Read) -> io::Result<Self> {
+        let mut header = [0; 4];
+        reader.read_exact(&mut header)?;
+
+        if &header[..3] != b"VOL" || header[3] != 3 {
+            return Err(invalid_data("not a version 3 .vol file"));
+        }
+        if read_i32(reader)? != 1 {
+            return Err(invalid_data("only float32 .vol files are supported"));
+        }
+
+        let mut resolution = [0; 3];
+        for size in &mut resolution {
+            *size = usize::try_from(read_i32(reader)?)
+                .map_err(|_| invalid_data("negative grid resolution"))?;
+        }
+
+        let channels = read_i32(reader)?;
+        if channels != 1 && channels != 4 {
+            return Err(invalid_data("expected 1 or 4 channels"));
+        }
+
+        // The bounding box stored in the file is ignored; the medium decides where the grid goes.
+        read_f32s(reader, 6)?;
+
+        let count = voxel_count(resolution)?;
+        let values = read_f32s(reader, checked_product(&[count, channels as usize])?)?;
+
+        Ok(if channels == 1 {
+            Self::new(resolution, values, None)
+        } else {
+            let voxels = values.chunks_exact(4);
+            let density = voxels.clone().map(|v| v[0]).collect();
+            let emission = voxels.map(|v| Color::new(v[1], v[2], v[3])).collect();
+
+            Self::new(resolution, density, Some(emission))
+        })
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.resolution[1] + y) * self.resolution[0] + x
+    }
+
+    /// Trilinearly interpolates `values` at a point in unit cube coordinates.
+    fn lookup<T>(&self, values: &[T], point: Point3) -> T
+    where
+        T: Copy + std::ops::Mul<f64, Output = T> + std::ops::Add<Output = T>,
+    {
+        let mut base = [0; 3];
+        let mut next = [0; 3];
+        let mut frac = [0.0; 3];
+
+        for axis in 0..3 {
+            let size = self.resolution[axis];
+            let p = (point[axis] * size as f64 - 0.5).clamp(0.0, (size - 1) as f64);
+
+            base[axis] = p.floor() as usize;
+            next[axis] = (base[axis] + 1).min(size - 1);
+            frac[axis] = p - base[axis] as f64;
+        }
+
+        let corner = |cx: bool, cy: bool, cz: bool| {
+            let x = if cx { next[0] } else { base[0] };
+            let y = if cy { next[1] } else { base[1] };
+            let z = if cz { next[2] } else { base[2] };
+
+            let weight = (if cx { frac[0] } else { 1.0 - frac[0] })
+                * (if cy { frac[1] } else { 1.0 - frac[1] })
+                * (if cz { frac[2] } else { 1.0 - frac[2] });
+
+            values[self.index(x, y, z)] * weight
+        };
+
+        corner(false, false, false)
+            + corner(true, false, false)
+            + corner(false, true, false)
+            + corner(true, true, false)
+            + corner(false, false, true)
+            + corner(true, false, true)
+            + corner(false, true, true)
+            + corner(true, true, true)
+    }
+
+    pub fn density(&self, point: Point3) -> f64 {
+        self.lookup(&self.density, point)
+    }
+
+    pub fn emission(&self, point: Point3) -> Color {
+        match &self.emission {
+            Some(emission) => self.lookup(emission, point),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn max_density(&self) -> f64 {
+        self.density.iter().copied().fold(0.0, f64::max)
+    }
+
+    /// The part of the unit cube containing any density, or `None` if the grid is empty.
+    pub fn occupied_bounds(&self) -> Option<Aabb> {
+        let [nx, ny, nz] = self.resolution;
+        let mut bounds: Option<Aabb> = None;
+
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    if self.density[self.index(x, y, z)] <= 0.0 {
+                        continue;
+                    }
+
+                    // Interpolation spreads each voxel's density out to its neighbours' centers
+                    let voxel = Aabb::new(
+                        Point3::new(
+                            (x as f64 - 0.5) / nx as f64,
+                            (y as f64 - 0.5) / ny as f64,
+                            (z as f64 - 0.5) / nz as f64,
+                        ),
+                        Point3::new(
+                            (x as f64 + 1.5) / nx as f64,
+                            (y as f64 + 1.5) / ny as f64,
+                            (z as f64 + 1.5) / nz as f64,
+                        ),
+                    );
+
+                    bounds = Some(bounds.map_or(voxel, |b| Aabb::surrounding(b, voxel)));
+                }
+            }
+        }
+
+        bounds.map(|b| {
+            Aabb::new(
+                b.minimum.max(&Point3::new(0.0, 0.0, 0.0)),
+                b.maximum.min(&Point3::new(1.0, 1.0, 1.0)),
+            )
+        })
+    }
+}
+
+/// A voxel grid stretched over `bounds`, scattering light with a Henyey–Greenstein phase function.
+///
+/// Collisions are found by delta tracking against the grid's maximum density, after clipping rays to the part of
+/// the grid that isn't empty. Only delta tracking is supported: paths carry on from real collisions, and there are
+/// no shadow rays that would need the transmittance along them estimated (by ratio tracking). At each collision a
+/// fraction `albedo` of the light is scattered and the rest is absorbed, with the absorbed fraction also giving off
+/// the grid's emission scaled by `emission_scale`.
+pub struct HeterogeneousMedium {
+    pub grid: VoxelGrid,
+    pub bounds: Aabb,
+    pub density_scale: f64,
+    pub albedo: Color,
+    pub g: f64,
+    pub emission_scale: f64,
+    majorant: f64,
+    occupied: Option<Aabb>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        grid: VoxelGrid,
+        bounds: Aabb,
+        density_scale: f64,
+        albedo: Color,
+        g: f64,
+    ) -> Box<Self> {
+        let majorant = grid.max_density() * density_scale;
+        let occupied = grid.occupied_bounds().map(|occupied| {
+            Aabb::new(
+                bounds.minimum + occupied.minimum * bounds.size(),
+                bounds.minimum + occupied.maximum * bounds.size(),
+            )
+        });
+
+        Box::new(Self {
+            grid,
+            bounds,
+            density_scale,
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+            emission_scale: 1.0,
+            majorant,
+            occupied,
+        })
+    }
+
+    fn to_grid(&self, point: Point3) -> Point3 {
+        let size = self.bounds.size();
+        let offset = point - self.bounds.minimum;
+
+        Point3::new(
+            offset.x() / size.x(),
+            offset.y() / size.y(),
+            offset.z() / size.z(),
+        )
+    }
+
+    pub fn density(&self, point: Point3) -> f64 {
+        self.grid.density(self.to_grid(point)) * self.density_scale
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+
+        let (t_enter, t_exit) = self.occupied?.clip(ray, t_min, t_max)?;
+
+        let mut rng = thread_rng();
+        let ray_length = ray.direction.length();
+        let mut t = t_enter;
+
+        loop {
+            t += -(1.0 - rng.gen::<f64>()).ln() / (self.majorant * ray_length);
+
+            if t >= t_exit {
+                return None;
+            }
+
+            // Tentative collisions against the majorant are real with probability density / majorant
+            let point = ray.at(t);
+            if rng.gen::<f64>() * self.majorant < self.density(point) {
+                return Some(HitRecord::new(
+                    point,
+                    Vector3::new(1.0, 0.0, 0.0),
+                    t,
+                    true,
+                    self,
+                ));
+            }
+        }
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+impl Material for HeterogeneousMedium {
+    fn scatter(&self, ray: Ray, rec: HitRecord, rng: &mut ThreadRng) -> Option<(Color, Ray)> {
+        Some((
+            self.albedo,
+            Ray {
+                origin: rec.point,
+                direction: HenyeyGreenstein::sample_direction(self.g, ray.direction, rng),
+                ..ray
+            },
+        ))
+    }
+
+    fn emitted(&self, _ray: Ray, rec: HitRecord) -> Color {
+        let absorbed = Color::new(1.0, 1.0, 1.0) - self.albedo;
+
+        self.emission_scale * absorbed * self.grid.emission(self.to_grid(rec.point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        aabb::Aabb,
+        hittables::Hittable,
+        math::{Color, Point3, Ray, Vector3},
+        volumes::{HeterogeneousMedium, VoxelGrid},
+    };
+
+    #[test]
+    fn lookup_interpolates_between_voxel_centers() {
+        let grid = VoxelGrid::new([2, 1, 1], vec![0.0, 1.0], None);
+
+        assert_eq!(grid.density(Point3::new(0.25, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.density(Point3::new(0.5, 0.5, 0.5)), 0.5);
+        assert_eq!(grid.density(Point3::new(0.75, 0.5, 0.5)), 1.0);
+    }
+
+    #[test]
+    fn read_vol_rejects_grids_too_large_to_index() {
+        let mut bytes = b"VOL\x03".to_vec();
+        for value in [1, i32::MAX, i32::MAX, i32::MAX, 4] {
+            bytes.extend(i32::to_le_bytes(value));
+        }
+        bytes.extend([0; 24]);
+
+        let result = VoxelGrid::read_vol(&mut bytes.as_slice());
+
+        assert!(matches!(result, Err(e) if e.kind() == std::io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn read_vol_reports_truncated_data() {
+        let mut bytes = b"VOL\x03".to_vec();
+        for value in [1, 100_000, 100_000, 100_000, 1] {
+            bytes.extend(i32::to_le_bytes(value));
+        }
+        bytes.extend([0; 24]);
+
+        let result = VoxelGrid::read_vol(&mut bytes.as_slice());
+
+        assert!(matches!(result, Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn read_vol_rejects_empty_grids() {
+        let mut bytes = b"VOL\x03".to_vec();
+        for value in [1, 4, 0, 4, 1] {
+            bytes.extend(i32::to_le_bytes(value));
+        }
+        bytes.extend([0; 24]);
+
+        let result = VoxelGrid::read_vol(&mut bytes.as_slice());
+
+        assert!(matches!(result, Err(e) if e.kind() == std::io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn read_raw_rejects_empty_grids() {
+        let result = VoxelGrid::read_raw(&mut [0; 16].as_slice(), [0, 2, 2]);
+
+        assert!(matches!(result, Err(e) if e.kind() == std::io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    #[should_panic(expected = "grid has no voxels")]
+    fn new_rejects_empty_grids() {
+        VoxelGrid::new([2, 0, 2], Vec::new(), None);
+    }
+
+    #[test]
+    fn read_vol_parses_header_and_data() {
+        let mut bytes = b"VOL\x03".to_vec();
+        for value in [1, 1, 1, 2, 1] {
+            bytes.extend(i32::to_le_bytes(value));
+        }
+        for value in [0.0f32, 0.0, 0.0, 1.0, 1.0, 1.0, 0.25, 0.75] {
+            bytes.extend(f32::to_le_bytes(value));
+        }
+
+        let grid = VoxelGrid::read_vol(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(grid.resolution, [1, 1, 2]);
+        assert_eq!(grid.density(Point3::new(0.5, 0.5, 0.0)), 0.25);
+    }
+
+    #[test]
+    fn delta_tracking_matches_beer_lambert() {
+        let grid = VoxelGrid::from_fn([4, 4, 4], |_| (1.0, Color::new(0.0, 0.0, 0.0)));
+        let medium = HeterogeneousMedium::new(
+            grid,
+            Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+            0.7,
+            Color::new(1.0, 1.0, 1.0),
+            0.0,
+        );
+        let ray = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vector3::new(1.0, 0.0, 0.0));
+
+        let samples = 100_000;
+        let passed = (0..samples)
+            .filter(|_| medium.hit(ray, 0.0, f64::INFINITY).is_none())
+            .count();
+        let fraction = passed as f64 / samples as f64;
+
+        assert!((fraction - (-0.7f64).exp()).abs() < 0.01, "{fraction}");
+    }
+}